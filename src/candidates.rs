@@ -0,0 +1,84 @@
+use std::convert::TryInto;
+use std::fmt;
+use std::num::NonZeroU8;
+
+// The digits that may still be placed in a cell, stored with digit d at bit d - 1.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Candidates(u32);
+
+impl Candidates {
+    pub fn empty() -> Self {
+        Candidates(0)
+    }
+
+    // All digits from 1 to length inclusive.
+    pub fn all(length: usize) -> Self {
+        assert!(length <= 32, "candidates only support up to 32 digits");
+        if length == 0 {
+            return Self::empty();
+        }
+        Candidates(u32::MAX >> (32 - length))
+    }
+
+    pub fn single(digit: NonZeroU8) -> Self {
+        Candidates(Self::bit(digit))
+    }
+
+    pub fn contains(self, digit: NonZeroU8) -> bool {
+        self.0 & Self::bit(digit) != 0
+    }
+
+    pub fn insert(&mut self, digit: NonZeroU8) {
+        self.0 |= Self::bit(digit);
+    }
+
+    // Returns whether the digit was a candidate before the removal.
+    pub fn remove(&mut self, digit: NonZeroU8) -> bool {
+        let present = self.contains(digit);
+        self.0 &= !Self::bit(digit);
+        present
+    }
+
+    pub fn union(self, other: Candidates) -> Candidates {
+        Candidates(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Candidates) -> Candidates {
+        Candidates(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: Candidates) -> Candidates {
+        Candidates(self.0 & !other.0)
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones().try_into().unwrap()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // Digits in ascending order.
+    pub fn iter(self) -> impl Iterator<Item = NonZeroU8> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let digit: u8 = (bits.trailing_zeros() + 1).try_into().unwrap();
+            bits &= bits - 1;
+            Some(digit.try_into().unwrap())
+        })
+    }
+
+    fn bit(digit: NonZeroU8) -> u32 {
+        1 << (digit.get() - 1)
+    }
+}
+
+impl fmt::Debug for Candidates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
@@ -0,0 +1,518 @@
+use crate::rules::util::offset;
+use crate::rules::util::{DIAGONAL_MOVES, KNIGHTS_MOVES, ORTHOGONAL_MOVES};
+use crate::rules::{
+    ClassicSudoku, EvenOddNeighbors, KnightsRestrictionSudoku, Miracle, ParityMask,
+};
+use crate::Board;
+use crate::Cell;
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+
+// A boolean formula in conjunctive normal form over one variable per cell and digit.
+// The variable for placing digit d in the cell at index i is i * LENGTH + d.
+pub struct Cnf<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+> {
+    clauses: Vec<Vec<i32>>,
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    // Every cell holds exactly one digit and the filled cells of the board keep their digits.
+    pub fn new(board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> Self {
+        let mut cnf = Cnf {
+            clauses: Vec::new(),
+        };
+        for (i, cell) in board.cells.iter().enumerate() {
+            let placements: Vec<_> = (1..=LENGTH).map(|d| (i, d)).collect();
+            cnf.exactly_one(&placements);
+            if let Cell::Filled(v) = cell {
+                cnf.add_clause(vec![Self::variable(i, v.get().into())]);
+            }
+        }
+        cnf
+    }
+
+    pub fn variable(index: usize, digit: usize) -> i32 {
+        (index * LENGTH + digit).try_into().unwrap()
+    }
+
+    pub fn num_variables(&self) -> usize {
+        NUM_CELLS * LENGTH
+    }
+
+    pub fn add_clause(&mut self, clause: Vec<i32>) {
+        self.clauses.push(clause);
+    }
+
+    // Exactly one of the (index, digit) placements holds.
+    pub fn exactly_one(&mut self, placements: &[(usize, usize)]) {
+        self.add_clause(
+            placements
+                .iter()
+                .map(|&(i, d)| Self::variable(i, d))
+                .collect(),
+        );
+        for (n, &(i1, d1)) in placements.iter().enumerate() {
+            for &(i2, d2) in placements[n + 1..].iter() {
+                self.forbid_pair(i1, d1, i2, d2);
+            }
+        }
+    }
+
+    // Digit d1 at index i1 and digit d2 at index i2 cannot both hold.
+    pub fn forbid_pair(&mut self, i1: usize, d1: usize, i2: usize, d2: usize) {
+        self.add_clause(vec![-Self::variable(i1, d1), -Self::variable(i2, d2)]);
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let mut s = String::new();
+        writeln!(s, "p cnf {} {}", self.num_variables(), self.clauses.len()).unwrap();
+        for clause in self.clauses.iter() {
+            for literal in clause.iter() {
+                write!(s, "{} ", literal).unwrap();
+            }
+            s.push_str("0\n");
+        }
+        s
+    }
+}
+
+pub trait CnfRules<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>
+{
+    fn encode(&self, cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>);
+}
+
+pub fn to_dimacs<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl CnfRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> String {
+    let mut cnf = Cnf::new(board);
+    rules.encode(&mut cnf);
+    cnf.to_dimacs()
+}
+
+// Why a SAT solver model could not be read as a board. Lines, rows and columns are zero-based and
+// displayed counting from one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModelError {
+    // The solver reported that the formula has no satisfying assignment.
+    Unsatisfiable,
+    // The solver gave up without deciding the formula.
+    Unknown,
+    // A literal which is not a number, is not a variable of the board or gives a second digit to
+    // a cell.
+    InvalidLiteral { line: usize, literal: String },
+    // No variable of the cell is true.
+    Unassigned { row: usize, column: usize },
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Unsatisfiable => f.write_str("The model is unsatisfiable"),
+            ModelError::Unknown => f.write_str("The model is unknown"),
+            ModelError::InvalidLiteral { line, literal } => write!(
+                f,
+                "Invalid literal {} on line {} of the model",
+                literal,
+                line + 1
+            ),
+            ModelError::Unassigned { row, column } => write!(
+                f,
+                "No digit for row {}, column {} in the model",
+                row + 1,
+                column + 1
+            ),
+        }
+    }
+}
+
+impl Error for ModelError {}
+
+// Reads a satisfying assignment as printed by SAT solvers, either as bare literals or on lines
+// starting with "v", after an optional "s" or bare status line. Every cell must be given a digit.
+pub fn from_dimacs_model<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    s: &str,
+) -> Result<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>, ModelError> {
+    let mut board = Board::unfilled();
+    for (number, line) in s.lines().enumerate() {
+        let line = line.trim();
+        let status = match line.chars().next() {
+            None | Some('c') => continue,
+            Some('s') => line[1..].trim(),
+            _ => line,
+        };
+        match status {
+            "SATISFIABLE" | "SAT" => continue,
+            "UNSATISFIABLE" | "UNSAT" => return Err(ModelError::Unsatisfiable),
+            "UNKNOWN" | "INDET" => return Err(ModelError::Unknown),
+            _ if line.starts_with('s') => return Err(ModelError::Unknown),
+            _ => {}
+        }
+        let literals = line.strip_prefix('v').unwrap_or(line);
+        for token in literals.split_whitespace() {
+            let invalid = || ModelError::InvalidLiteral {
+                line: number,
+                literal: token.to_string(),
+            };
+            let literal: i64 = token.parse().map_err(|_| invalid())?;
+            let variable = literal.unsigned_abs();
+            if variable > (NUM_CELLS * LENGTH) as u64 {
+                return Err(invalid());
+            }
+            // Zero ends the model and false variables place nothing.
+            if literal <= 0 {
+                continue;
+            }
+            let variable = (variable - 1) as usize;
+            let index = variable / LENGTH;
+            let digit: u8 = (variable % LENGTH + 1).try_into().unwrap();
+            if board.cells[index] != Cell::Unfilled {
+                return Err(invalid());
+            }
+            board.cells[index] = Cell::Filled(digit.try_into().unwrap());
+        }
+    }
+    if let Some(index) = board.cells.iter().position(|&c| c == Cell::Unfilled) {
+        return Err(ModelError::Unassigned {
+            row: index / LENGTH,
+            column: index % LENGTH,
+        });
+    }
+    board.reset_candidates();
+    Ok(board)
+}
+
+pub fn encode_classic<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) {
+    encode_irregular(cnf);
+    // Each digit appears exactly once in each block.
+    for block in 0..LENGTH {
+        let top = block / (LENGTH / BOX_WIDTH) * BOX_HEIGHT;
+        let left = block % (LENGTH / BOX_WIDTH) * BOX_WIDTH;
+        for d in 1..=LENGTH {
+            let placements: Vec<_> = (0..LENGTH)
+                .map(|i| ((top + i / BOX_WIDTH) * LENGTH + left + i % BOX_WIDTH, d))
+                .collect();
+            cnf.exactly_one(&placements);
+        }
+    }
+}
+
+pub fn encode_irregular<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) {
+    // Each digit appears exactly once in each row and column.
+    for line in 0..LENGTH {
+        for d in 1..=LENGTH {
+            let row: Vec<_> = (0..LENGTH).map(|i| (line * LENGTH + i, d)).collect();
+            let column: Vec<_> = (0..LENGTH).map(|i| (i * LENGTH + line, d)).collect();
+            cnf.exactly_one(&row);
+            cnf.exactly_one(&column);
+        }
+    }
+}
+
+pub fn encode_knights_move<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) {
+    encode_same_digit_moves(cnf, &KNIGHTS_MOVES);
+}
+
+pub fn encode_kings_move<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) {
+    encode_same_digit_moves(cnf, &DIAGONAL_MOVES);
+}
+
+pub fn encode_nonconsecutive<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) {
+    for (i, j) in move_pairs::<LENGTH>(NUM_CELLS, &ORTHOGONAL_MOVES) {
+        for d in 1..LENGTH {
+            cnf.forbid_pair(i, d, j, d + 1);
+            cnf.forbid_pair(i, d + 1, j, d);
+        }
+    }
+}
+
+// Cells a move apart cannot contain the same digit.
+fn encode_same_digit_moves<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    moves: &[(isize, isize)],
+) {
+    for (i, j) in move_pairs::<LENGTH>(NUM_CELLS, moves) {
+        for d in 1..=LENGTH {
+            cnf.forbid_pair(i, d, j, d);
+        }
+    }
+}
+
+// Each unordered pair of cells a move apart, listed once.
+fn move_pairs<const LENGTH: usize>(
+    num_cells: usize,
+    moves: &[(isize, isize)],
+) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..num_cells {
+        for &(dr, dc) in moves.iter() {
+            match offset::<LENGTH>(i, dr, dc) {
+                Some(j) if j > i => pairs.push((i, j)),
+                _ => (),
+            }
+        }
+    }
+    pairs
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > CnfRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> for ClassicSudoku
+{
+    fn encode(&self, cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) {
+        encode_classic(cnf);
+    }
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > CnfRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> for KnightsRestrictionSudoku
+{
+    fn encode(&self, cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) {
+        encode_classic(cnf);
+        encode_knights_move(cnf);
+    }
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > CnfRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> for Miracle
+{
+    fn encode(&self, cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) {
+        encode_classic(cnf);
+        encode_knights_move(cnf);
+        encode_kings_move(cnf);
+        encode_nonconsecutive(cnf);
+    }
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > CnfRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+    for ParityMask<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    fn encode(&self, cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) {
+        encode_classic(cnf);
+        // Masked cells cannot hold a digit of the other parity.
+        for (i, cell) in self.mask.cells.iter().enumerate() {
+            if let Cell::Filled(v) = cell {
+                let parity = usize::from(v.get() % 2);
+                for d in (1..=LENGTH).filter(|d| d % 2 != parity) {
+                    cnf.add_clause(vec![
+                        -Cnf::<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>::variable(i, d),
+                    ]);
+                }
+            }
+        }
+    }
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > CnfRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> for EvenOddNeighbors
+{
+    fn encode(&self, cnf: &mut Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) {
+        encode_classic(cnf);
+        // Orthogonally adjacent cells cannot both be even.
+        for (i, j) in move_pairs::<LENGTH>(NUM_CELLS, &ORTHOGONAL_MOVES) {
+            for d1 in (2..=LENGTH).step_by(2) {
+                for d2 in (2..=LENGTH).step_by(2) {
+                    cnf.forbid_pair(i, d1, j, d2);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satisfies<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    >(
+        cnf: &Cnf<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    ) -> bool {
+        let holds = |literal: i32| {
+            let variable: usize = (literal.abs() - 1).try_into().unwrap();
+            let placed = board.cells[variable / LENGTH]
+                == Cell::Filled(((variable % LENGTH + 1) as u8).try_into().unwrap());
+            placed == (literal > 0)
+        };
+        cnf.clauses
+            .iter()
+            .all(|clause| clause.iter().any(|&l| holds(l)))
+    }
+
+    #[test]
+    fn test_classic_encoding() {
+        let puzzle: Board<81, 9, 3, 3> =
+            "000075400000000008080190000300001060000000034000068170204000603900000020530200000"
+                .parse()
+                .unwrap();
+        let solved: Board<81, 9, 3, 3> =
+            "693875412145632798782194356357421869816957234429368175274519683968743521531286947"
+                .parse()
+                .unwrap();
+        let mut cnf = Cnf::new(&puzzle);
+        ClassicSudoku {}.encode(&mut cnf);
+        assert!(satisfies(&cnf, &solved));
+        let mut wrong = solved.clone();
+        wrong.cells.swap(0, 1);
+        assert!(!satisfies(&cnf, &wrong));
+        assert!(to_dimacs(&puzzle, &ClassicSudoku {}).starts_with("p cnf 729 "));
+    }
+
+    #[test]
+    fn test_miracle_encoding() {
+        let solved: Board<81, 9, 3, 3> =
+            "147582936582936471936471825471825369825369714369714258714258693258693147693147582"
+                .parse()
+                .unwrap();
+        let mut cnf = Cnf::new(&Board::unfilled());
+        Miracle {}.encode(&mut cnf);
+        assert!(satisfies(&cnf, &solved));
+        let mut cnf = Cnf::new(&Board::unfilled());
+        EvenOddNeighbors {}.encode(&mut cnf);
+        assert!(!satisfies(&cnf, &solved));
+    }
+
+    #[test]
+    fn test_read_model() {
+        let solved: Board<16, 4, 2, 2> = "1234341221434321".parse().unwrap();
+        let mut model = String::from("s SATISFIABLE\nv");
+        for i in 0..16 {
+            for d in 1..=4 {
+                let variable = Cnf::<16, 4, 2, 2>::variable(i, d);
+                let placed = solved.cells[i] == Cell::Filled((d as u8).try_into().unwrap());
+                write!(model, " {}", if placed { variable } else { -variable }).unwrap();
+            }
+        }
+        model.push_str(" 0\n");
+        assert_eq!(from_dimacs_model(&model).unwrap(), solved);
+        assert!(from_dimacs_model::<16, 4, 2, 2>("v 1 2 0").is_err());
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>("s SATISFIABLE\nv 1 65 0").unwrap_err(),
+            ModelError::InvalidLiteral {
+                line: 1,
+                literal: "65".to_string()
+            }
+        );
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>("v -1000 0").unwrap_err(),
+            ModelError::InvalidLiteral {
+                line: 0,
+                literal: "-1000".to_string()
+            }
+        );
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>("s UNSATISFIABLE\n").unwrap_err(),
+            ModelError::Unsatisfiable
+        );
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>("UNSAT\n").unwrap_err(),
+            ModelError::Unsatisfiable
+        );
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>("s UNKNOWN\n").unwrap_err(),
+            ModelError::Unknown
+        );
+        let unassigned = model.replacen(" 1 ", " -1 ", 1);
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>(&unassigned).unwrap_err(),
+            ModelError::Unassigned { row: 0, column: 0 }
+        );
+        assert_eq!(
+            from_dimacs_model::<16, 4, 2, 2>("v 1 x")
+                .unwrap_err()
+                .to_string(),
+            "Invalid literal x on line 1 of the model"
+        );
+    }
+}
@@ -1,18 +1,33 @@
+use crate::rating::{rate, Band, Rating};
 use crate::rules::PuzzleRules;
-use crate::solve::solve;
+use crate::search::{shuffle, Search, SearchBudget, SearchStats};
 use crate::solve::SolveResult;
+use crate::solve::{first_two, solutions_with_budget};
 use crate::Board;
 use crate::Cell;
-use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
-use std::convert::TryInto;
+use rand::{thread_rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::error::Error;
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::time::Instant;
 
 #[derive(Debug)]
 pub enum PuzzleCreateError {
     NoSolution,
     MultipleSolutions,
+    // The budget ran out, with the statistics of every search made so far.
+    Aborted(SearchStats),
+    // Every attempt produced a puzzle outside the target difficulty.
+    DifficultyNotMet,
+    // No puzzle could be reduced to the maximum number of givens. Holds the fewest reached.
+    TooManyGivens(usize),
+    // Keeping only the givens inside the mask never left a unique solution.
+    MaskNotUnique,
+    // The mask holds an index which is not a cell of the board.
+    MaskOutOfRange(usize),
+    // Creation was asked to make no attempts.
+    NoAttempts,
 }
 
 impl fmt::Display for PuzzleCreateError {
@@ -20,6 +35,12 @@ impl fmt::Display for PuzzleCreateError {
         let error = match self {
             Self::NoSolution => "No solution",
             Self::MultipleSolutions => "Multiple solutions",
+            Self::Aborted(_) => "Search aborted",
+            Self::DifficultyNotMet => "Difficulty not met",
+            Self::TooManyGivens(_) => "Too many givens",
+            Self::MaskNotUnique => "No unique solution within mask",
+            Self::MaskOutOfRange(_) => "Mask index out of range",
+            Self::NoAttempts => "No attempts allowed",
         };
         f.write_str(error)
     }
@@ -35,114 +56,559 @@ pub fn create_puzzle_solution<
 >(
     rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
 ) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
-    let mut rng = thread_rng();
-    create_puzzle_solution_recursive(&mut Board::unfilled(), rules, &mut rng)
+    create_puzzle_solution_with_rng(rules, &mut thread_rng())
 }
 
-fn create_puzzle_solution_recursive<
+pub fn create_puzzle_solution_with_rng<
     const NUM_CELLS: usize,
     const LENGTH: usize,
     const BOX_WIDTH: usize,
     const BOX_HEIGHT: usize,
 >(
-    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
     rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
-    rng: &mut impl Rng,
+    rng: &mut dyn RngCore,
 ) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
-    if !rules.is_valid(board) {
-        return None;
+    let budget = SearchBudget::unlimited();
+    // An unlimited budget never runs out.
+    BudgetedSolver::new(rules, &budget).solution(rng).unwrap()
+}
+
+// Only the seed and the budget of the options apply to a solution.
+pub fn create_puzzle_solution_with_options<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    options: &CreateOptions,
+) -> Result<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>, PuzzleCreateError> {
+    let mut solver = BudgetedSolver::new(rules, &options.budget);
+    let solution = match options.seed {
+        Some(seed) => solver.solution(&mut seeded_rng(seed)),
+        None => solver.solution(&mut thread_rng()),
+    };
+    solution?.ok_or(PuzzleCreateError::NoSolution)
+}
+
+// A generator which produces the same numbers for a seed on every platform. Creating a puzzle
+// with it gives the same board for the same seed and version of this crate.
+pub fn seeded_rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+pub fn create_puzzle_from<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> Result<(), PuzzleCreateError> {
+    create_puzzle_from_with_budget(board, rules, &SearchBudget::unlimited())
+}
+
+// The budget is shared by every uniqueness check made while removing digits.
+pub fn create_puzzle_from_with_budget<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    budget: &SearchBudget,
+) -> Result<(), PuzzleCreateError> {
+    let options = CreateOptions::new().budget(budget.clone());
+    create_puzzle_from_with_options(board, rules, &options)
+}
+
+pub fn create_puzzle_from_with_options<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    options: &CreateOptions,
+) -> Result<(), PuzzleCreateError> {
+    match options.seed {
+        Some(seed) => create_puzzle_from_with_rng(board, rules, options, &mut seeded_rng(seed)),
+        None => create_puzzle_from_with_rng(board, rules, options, &mut thread_rng()),
     }
-    // Find an empty cell.
-    let index = board
-        .cells
-        .iter()
-        .enumerate()
-        .find(|(_i, &cell)| matches!(cell, Cell::Unfilled))
-        .map(|e| e.0);
-    let index = match index {
-        None => return Some(board.clone()),
+}
+
+// Ignores the seed of the options in favor of the given generator.
+pub fn create_puzzle_from_with_rng<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    options: &CreateOptions,
+    rng: &mut dyn RngCore,
+) -> Result<(), PuzzleCreateError> {
+    let mut solver = BudgetedSolver::new(rules, &options.budget);
+    remove_digits(board, &mut solver, options, rng)
+}
+
+// How the givens of a created puzzle map onto each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Symmetry {
+    #[default]
+    None,
+    // Rotating the grid by 180 degrees.
+    Rotational180,
+    // Rotating the grid by 90 degrees.
+    Rotational90,
+    // Mirroring the top half onto the bottom half.
+    Horizontal,
+    // Mirroring the left half onto the right half.
+    Vertical,
+    // Mirroring across the diagonal from the top left corner.
+    Diagonal,
+    // Mirroring across the diagonal from the top right corner.
+    AntiDiagonal,
+}
+
+impl Symmetry {
+    // Groups the cells of a grid with the given side length into the sets the symmetry maps onto
+    // each other. Every cell belongs to exactly one orbit.
+    pub fn orbits(self, length: usize) -> Vec<Vec<usize>> {
+        let mut seen = vec![false; length * length];
+        let mut orbits = Vec::new();
+        for start in 0..length * length {
+            if seen[start] {
+                continue;
+            }
+            let mut orbit = Vec::new();
+            let mut i = start;
+            while !seen[i] {
+                seen[i] = true;
+                orbit.push(i);
+                i = self.image(length, i);
+            }
+            orbits.push(orbit);
+        }
+        orbits
+    }
+
+    fn image(self, length: usize, index: usize) -> usize {
+        let (row, column) = (index / length, index % length);
+        let last = length - 1;
+        let (row, column) = match self {
+            Symmetry::None => (row, column),
+            Symmetry::Rotational180 => (last - row, last - column),
+            Symmetry::Rotational90 => (column, last - row),
+            Symmetry::Horizontal => (last - row, column),
+            Symmetry::Vertical => (row, last - column),
+            Symmetry::Diagonal => (column, row),
+            Symmetry::AntiDiagonal => (last - column, last - row),
+        };
+        row * length + column
+    }
+}
+
+// Settings for removing digits from a solution, built up like a SearchBudget.
+#[derive(Clone, Debug, Default)]
+pub struct CreateOptions {
+    symmetry: Symmetry,
+    minimal: bool,
+    seed: Option<u64>,
+    max_givens: Option<usize>,
+    mask: Option<Vec<usize>>,
+    budget: SearchBudget,
+}
+
+impl CreateOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn symmetry(mut self, symmetry: Symmetry) -> Self {
+        self.symmetry = symmetry;
+        self
+    }
+
+    // Without symmetry the puzzle is always minimal. With symmetry, no whole orbit can be removed
+    // but single givens may be, unless this is set and such givens are removed too.
+    pub fn minimal(mut self, minimal: bool) -> Self {
+        self.minimal = minimal;
+        self
+    }
+
+    // Removes digits using seeded_rng(seed) instead of a thread local generator.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // Digits are removed in other orders until the puzzle has at most this many givens. Fails
+    // with TooManyGivens if none of them get there.
+    pub fn max_givens(mut self, max_givens: usize) -> Self {
+        self.max_givens = Some(max_givens);
+        self
+    }
+
+    // Only the cells at these indexes may keep their digits. Fails with MaskNotUnique if the
+    // digits in these cells do not lead to a unique solution and with MaskOutOfRange if an index
+    // is not a cell of the board.
+    pub fn mask(mut self, cells: impl IntoIterator<Item = usize>) -> Self {
+        self.mask = Some(cells.into_iter().collect());
+        self
+    }
+
+    // Shared by every search made while creating the puzzle, including the one for its solution.
+    pub fn budget(mut self, budget: SearchBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DifficultyTarget {
+    Band(Band),
+    Score(RangeInclusive<f32>),
+}
+
+impl DifficultyTarget {
+    pub fn contains(&self, rating: &Rating) -> bool {
+        match self {
+            Self::Band(band) => rating.band == *band,
+            Self::Score(range) => range.contains(&rating.score),
+        }
+    }
+}
+
+// Creates puzzles from fresh solutions until one meets the constraints of the options, trying at
+// most max_attempts times, which must be at least one. The budget of the options is shared across
+// the attempts.
+pub fn create_puzzle<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    max_attempts: usize,
+    options: &CreateOptions,
+) -> Result<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>, PuzzleCreateError> {
+    create_with_attempts(rules, None, max_attempts, options).map(|(board, _rating)| board)
+}
+
+// Same as create_puzzle but the puzzle must also be rated within the target.
+pub fn create_puzzle_with_difficulty<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    target: &DifficultyTarget,
+    max_attempts: usize,
+    options: &CreateOptions,
+) -> Result<(Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>, Rating), PuzzleCreateError> {
+    create_with_attempts(rules, Some(target), max_attempts, options)
+        .map(|(board, rating)| (board, rating.unwrap()))
+}
+
+fn create_with_attempts<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    target: Option<&DifficultyTarget>,
+    max_attempts: usize,
+    options: &CreateOptions,
+) -> Result<
+    (
+        Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        Option<Rating>,
+    ),
+    PuzzleCreateError,
+> {
+    if max_attempts == 0 {
+        return Err(PuzzleCreateError::NoAttempts);
+    }
+    let mut thread_rng = thread_rng();
+    let mut seeded_rng = options.seed.map(seeded_rng);
+    let rng: &mut dyn RngCore = match seeded_rng.as_mut() {
         Some(v) => v,
+        None => &mut thread_rng,
     };
-    let mut options: Vec<_> = (1..=LENGTH).collect();
-    options.shuffle(rng);
-    for &guess in options.iter() {
-        let guess: u8 = guess.try_into().unwrap();
-        board.cells[index] = Cell::Filled(guess.try_into().unwrap());
-        match create_puzzle_solution_recursive(board, rules, rng) {
-            None => (),
-            Some(b) => return Some(b),
+    let mut solver = BudgetedSolver::new(rules, &options.budget);
+    // Reported if every attempt fails, keeping the closest attempt for too many givens.
+    let mut error = PuzzleCreateError::DifficultyNotMet;
+    for _ in 0..max_attempts {
+        let mut board = solver.solution(rng)?.ok_or(PuzzleCreateError::NoSolution)?;
+        match remove_digits(&mut board, &mut solver, options, rng) {
+            Ok(()) => (),
+            Err(PuzzleCreateError::TooManyGivens(givens)) => {
+                if !matches!(error, PuzzleCreateError::TooManyGivens(fewest) if fewest <= givens) {
+                    error = PuzzleCreateError::TooManyGivens(givens);
+                }
+                continue;
+            }
+            Err(PuzzleCreateError::MaskNotUnique) => {
+                if !matches!(error, PuzzleCreateError::TooManyGivens(_)) {
+                    error = PuzzleCreateError::MaskNotUnique;
+                }
+                continue;
+            }
+            Err(e) => return Err(e),
+        }
+        let target = match target {
+            None => return Ok((board, None)),
+            Some(v) => v,
+        };
+        if let Some(rating) = rate(&board, rules) {
+            if target.contains(&rating) {
+                return Ok((board, Some(rating)));
+            }
         }
     }
-    // Make sure we exit this function with the board unchanged if we found no solution.
-    board.cells[index] = Cell::Unfilled;
-    None
+    Err(error)
 }
 
-pub fn create_puzzle_from<
+// Orders of removal tried on one solution before giving up on reaching the maximum givens.
+const MAX_GIVENS_ATTEMPTS: usize = 10;
+
+fn remove_digits<
     const NUM_CELLS: usize,
     const LENGTH: usize,
     const BOX_WIDTH: usize,
     const BOX_HEIGHT: usize,
 >(
     board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
-    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    solver: &mut BudgetedSolver<impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+    options: &CreateOptions,
+    rng: &mut dyn RngCore,
 ) -> Result<(), PuzzleCreateError> {
-    match solve(board, rules) {
+    if let Some(&i) = options.mask.iter().flatten().find(|&&i| i >= NUM_CELLS) {
+        return Err(PuzzleCreateError::MaskOutOfRange(i));
+    }
+    match solver.solve(board)? {
         SolveResult::NoSolution => return Err(PuzzleCreateError::NoSolution),
         SolveResult::MultipleSolutions(_) => return Err(PuzzleCreateError::MultipleSolutions),
         SolveResult::UniqueSolution(_) => (),
     }
-    // Keep removing digits while there exists a unique solution.
-    let mut rng = thread_rng();
-    while remove_digit(board, rules, &mut rng) {}
-    Ok(())
+    // Work on a copy so the board is left as it was if creation fails.
+    let mut start = board.clone();
+    if let Some(mask) = &options.mask {
+        for i in (0..NUM_CELLS).filter(|i| !mask.contains(i)) {
+            start.cells[i] = Cell::Unfilled;
+        }
+        if !matches!(solver.solve(&start)?, SolveResult::UniqueSolution(_)) {
+            return Err(PuzzleCreateError::MaskNotUnique);
+        }
+    }
+    // How many givens are left depends on the order digits are removed in, so try other orders
+    // when there are too many.
+    let attempts = if options.max_givens.is_some() {
+        MAX_GIVENS_ATTEMPTS
+    } else {
+        1
+    };
+    let mut fewest = NUM_CELLS;
+    for _ in 0..attempts {
+        let mut puzzle = start.clone();
+        remove_orbits(&mut puzzle, solver, &options.symmetry.orbits(LENGTH), rng)?;
+        if options.minimal && options.symmetry != Symmetry::None {
+            // Single givens may still be removable once no whole orbit is.
+            remove_orbits(&mut puzzle, solver, &Symmetry::None.orbits(LENGTH), rng)?;
+        }
+        let givens = puzzle
+            .cells
+            .iter()
+            .filter(|&&c| c != Cell::Unfilled)
+            .count();
+        if options
+            .max_givens
+            .map_or(true, |max_givens| givens <= max_givens)
+        {
+            *board = puzzle;
+            return Ok(());
+        }
+        fewest = fewest.min(givens);
+    }
+    Err(PuzzleCreateError::TooManyGivens(fewest))
+}
+
+// Runs successive searches against a single budget.
+struct BudgetedSolver<'a, R> {
+    rules: &'a R,
+    budget: &'a SearchBudget,
+    stats: SearchStats,
+    started: Instant,
 }
 
-fn remove_digit<
+impl<'a, R> BudgetedSolver<'a, R> {
+    fn new(rules: &'a R, budget: &'a SearchBudget) -> Self {
+        BudgetedSolver {
+            rules,
+            budget,
+            stats: SearchStats::default(),
+            started: Instant::now(),
+        }
+    }
+
+    fn solve<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    >(
+        &mut self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    ) -> Result<SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>, PuzzleCreateError>
+    where
+        R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    {
+        let mut solutions =
+            solutions_with_budget(board, self.rules, &self.budget.remaining(&self.stats));
+        let result = first_two(&mut solutions);
+        self.stats.accumulate(&solutions.stats());
+        self.stats.elapsed = self.started.elapsed();
+        if solutions.is_aborted() {
+            return Err(PuzzleCreateError::Aborted(self.stats.clone()));
+        }
+        Ok(result)
+    }
+
+    // A random solution of an empty board. Returns Ok(None) if the rules allow no solution.
+    fn solution<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    >(
+        &mut self,
+        rng: &mut dyn RngCore,
+    ) -> Result<Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>, PuzzleCreateError>
+    where
+        R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    {
+        // A random search can wander into a dead end which takes very long to back out of on large
+        // grids, so start over with a new order once it takes too many guesses, allowing more each
+        // time, until the budget runs out.
+        let mut max_nodes = 10 * NUM_CELLS as u64;
+        loop {
+            let budget = self.budget.remaining(&self.stats).limit_nodes(max_nodes);
+            let mut search = Search::shuffled(&Board::unfilled(), self.rules, &mut *rng, budget);
+            let solution = search.next_solution();
+            self.stats.accumulate(&search.stats());
+            self.stats.elapsed = self.started.elapsed();
+            if solution.is_some() || !search.is_aborted() {
+                return Ok(solution);
+            }
+            if self.budget.is_exhausted(&self.stats) {
+                return Err(PuzzleCreateError::Aborted(self.stats.clone()));
+            }
+            max_nodes *= 2;
+        }
+    }
+}
+
+// Removes the digits of each orbit in a random order as long as the solution stays unique.
+// Removing digits only adds solutions, so an orbit which cannot be removed now never can be later
+// and a single pass leaves no removable orbit.
+fn remove_orbits<
     const NUM_CELLS: usize,
     const LENGTH: usize,
     const BOX_WIDTH: usize,
     const BOX_HEIGHT: usize,
 >(
     board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
-    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
-    rng: &mut impl Rng,
-) -> bool {
-    let mut filled_indexes: Vec<usize> = board
-        .cells
+    solver: &mut BudgetedSolver<impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+    orbits: &[Vec<usize>],
+    rng: &mut dyn RngCore,
+) -> Result<(), PuzzleCreateError> {
+    let mut filled_orbits: Vec<Vec<usize>> = orbits
         .iter()
-        .enumerate()
-        .filter(|(_i, c)| matches!(c, Cell::Filled(_)))
-        .map(|e| e.0)
+        .map(|orbit| {
+            orbit
+                .iter()
+                .copied()
+                .filter(|&i| matches!(board.cells[i], Cell::Filled(_)))
+                .collect::<Vec<_>>()
+        })
+        .filter(|orbit| !orbit.is_empty())
         .collect();
-    filled_indexes.shuffle(rng);
-    let len = filled_indexes.len();
-    for i in filled_indexes {
+    shuffle(&mut filled_orbits, rng);
+    for orbit in filled_orbits {
+        let old_values: Vec<_> = orbit.iter().map(|&i| board.cells[i]).collect();
+        for &i in orbit.iter() {
+            board.cells[i] = Cell::Unfilled;
+        }
+        // The orbit is also put back if the budget runs out, which leaves a unique puzzle.
+        let result = solver.solve(board);
+        if !matches!(result, Ok(SolveResult::UniqueSolution(_))) {
+            for (&i, &v) in orbit.iter().zip(old_values.iter()) {
+                board.cells[i] = v;
+            }
+        }
+        result?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Minimality {
+    Minimal,
+    // The givens which can each be removed on their own while keeping the solution unique.
+    Removable(Vec<usize>),
+}
+
+// Checks that no single given of a puzzle with a unique solution can be removed.
+pub fn is_minimal<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> Result<Minimality, PuzzleCreateError> {
+    let budget = SearchBudget::unlimited();
+    let mut solver = BudgetedSolver::new(rules, &budget);
+    match solver.solve(board)? {
+        SolveResult::NoSolution => return Err(PuzzleCreateError::NoSolution),
+        SolveResult::MultipleSolutions(_) => return Err(PuzzleCreateError::MultipleSolutions),
+        SolveResult::UniqueSolution(_) => (),
+    }
+    let mut removable = Vec::new();
+    let mut board = board.clone();
+    for i in 0..NUM_CELLS {
         let old_value = board.cells[i];
+        if old_value == Cell::Unfilled {
+            continue;
+        }
         board.cells[i] = Cell::Unfilled;
-        match solve(board, rules) {
-            SolveResult::NoSolution => {
-                board.cells[i] = old_value;
-                return false;
-            }
-            SolveResult::UniqueSolution(_) => {
-                dbg!(board);
-                dbg!(len - 1);
-                return true;
-            }
-            SolveResult::MultipleSolutions(_) => {
-                board.cells[i] = old_value;
-                dbg!(i);
-            }
+        if let SolveResult::UniqueSolution(_) = solver.solve(&board)? {
+            removable.push(i);
         }
+        board.cells[i] = old_value;
+    }
+    if removable.is_empty() {
+        Ok(Minimality::Minimal)
+    } else {
+        Ok(Minimality::Removable(removable))
     }
-    false
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::rules::ClassicSudoku;
+    use crate::solve::solve;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
 
     #[test]
     fn test_create_solution() {
@@ -150,4 +616,234 @@ mod tests {
         let board: Board<81, 9, 3, 3> = create_puzzle_solution(&rules).unwrap();
         assert!(rules.is_valid(&board));
     }
+
+    #[test]
+    fn test_create_large_grids() {
+        let rules = ClassicSudoku {};
+        let options = CreateOptions::new()
+            .seed(1)
+            .symmetry(Symmetry::Rotational90);
+        let solution: Board<256, 16, 4, 4> =
+            create_puzzle_solution_with_options(&rules, &options).unwrap();
+        assert!(rules.is_valid(&solution));
+        // Whole orbits are removed at a time, which keeps the number of uniqueness checks down.
+        let mut puzzle = solution.clone();
+        create_puzzle_from_with_options(&mut puzzle, &rules, &options).unwrap();
+        assert!(puzzle.cells.contains(&Cell::Unfilled));
+        assert_eq!(
+            solve(&mut puzzle.clone(), &rules),
+            SolveResult::UniqueSolution(solution)
+        );
+
+        let solution: Board<625, 25, 5, 5> =
+            create_puzzle_solution_with_options(&rules, &CreateOptions::new().seed(1)).unwrap();
+        assert!(rules.is_valid(&solution));
+    }
+
+    #[test]
+    fn test_create_puzzle_budget() {
+        let rules = ClassicSudoku {};
+        let solution: Board<81, 9, 3, 3> = create_puzzle_solution(&rules).unwrap();
+        let mut board = solution.clone();
+        let budget = SearchBudget::unlimited().max_nodes(1000);
+        let result = create_puzzle_from_with_budget(&mut board, &rules, &budget);
+        assert!(matches!(result, Err(PuzzleCreateError::Aborted(stats)) if stats.nodes == 1000));
+        assert_eq!(board, solution);
+
+        // Finding the solution to start from counts against the budget too.
+        let options = CreateOptions::new().budget(SearchBudget::unlimited().max_nodes(5));
+        let result: Result<Board<81, 9, 3, 3>, _> = create_puzzle(&rules, 1, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::Aborted(stats)) if stats.nodes == 5));
+        let cancel = Arc::new(AtomicBool::new(true));
+        let options = CreateOptions::new().budget(SearchBudget::unlimited().cancel_flag(cancel));
+        let result: Result<Board<81, 9, 3, 3>, _> = create_puzzle(&rules, 1, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::Aborted(_))));
+    }
+
+    #[test]
+    fn test_create_puzzle_with_difficulty() {
+        let rules = ClassicSudoku {};
+        let target = DifficultyTarget::Band(Band::Easy);
+        let options = CreateOptions::new().seed(5);
+        let (board, rating): (Board<81, 9, 3, 3>, _) =
+            create_puzzle_with_difficulty(&rules, &target, 50, &options).unwrap();
+        assert_eq!(rating.band, Band::Easy);
+        assert_eq!(rate(&board, &rules), Some(rating));
+
+        // No puzzle rates this high.
+        let target = DifficultyTarget::Score(10.0..=11.0);
+        let result: Result<(Board<81, 9, 3, 3>, _), _> =
+            create_puzzle_with_difficulty(&rules, &target, 2, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::DifficultyNotMet)));
+        let options = options.budget(SearchBudget::unlimited().max_nodes(1000));
+        let result: Result<(Board<81, 9, 3, 3>, _), _> =
+            create_puzzle_with_difficulty(&rules, &target, 2, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::Aborted(_))));
+    }
+
+    #[test]
+    fn test_symmetry_orbits() {
+        assert_eq!(Symmetry::None.orbits(9).len(), 81);
+        assert_eq!(Symmetry::Rotational180.orbits(9).len(), 41);
+        assert_eq!(Symmetry::Rotational90.orbits(9).len(), 21);
+        assert_eq!(Symmetry::Horizontal.orbits(9).len(), 45);
+        assert_eq!(Symmetry::Diagonal.orbits(4).len(), 10);
+        assert!(Symmetry::AntiDiagonal.orbits(9).contains(&vec![1, 71]));
+    }
+
+    #[test]
+    fn test_create_symmetric_puzzle() {
+        let rules = ClassicSudoku {};
+        for &symmetry in [Symmetry::Rotational180, Symmetry::Rotational90].iter() {
+            let mut board: Board<81, 9, 3, 3> = create_puzzle_solution(&rules).unwrap();
+            let options = CreateOptions::new().symmetry(symmetry);
+            create_puzzle_from_with_options(&mut board, &rules, &options).unwrap();
+            for orbit in symmetry.orbits(9) {
+                let filled = orbit.iter().filter(|&&i| board.cells[i] != Cell::Unfilled);
+                assert!(filled.count() % orbit.len() == 0);
+            }
+            assert!(matches!(
+                crate::solve::solve(&mut board, &rules),
+                SolveResult::UniqueSolution(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_is_minimal() {
+        let rules = ClassicSudoku {};
+        let mut board: Board<81, 9, 3, 3> = create_puzzle_solution(&rules).unwrap();
+        create_puzzle_from(&mut board, &rules).unwrap();
+        assert_eq!(is_minimal(&board, &rules).unwrap(), Minimality::Minimal);
+
+        // A given added back from the solution is removable again.
+        let solution = match crate::solve::solve(&mut board.clone(), &rules) {
+            SolveResult::UniqueSolution(v) => v,
+            _ => unreachable!(),
+        };
+        let i = (0..81).find(|&i| board.cells[i] == Cell::Unfilled).unwrap();
+        board.cells[i] = solution.cells[i];
+        assert!(matches!(
+            is_minimal(&board, &rules).unwrap(),
+            Minimality::Removable(v) if v.contains(&i)
+        ));
+        assert!(matches!(
+            is_minimal(&Board::<81, 9, 3, 3>::unfilled(), &rules),
+            Err(PuzzleCreateError::MultipleSolutions)
+        ));
+    }
+
+    #[test]
+    fn test_create_minimal_symmetric_puzzle() {
+        let rules = ClassicSudoku {};
+        let mut board: Board<81, 9, 3, 3> = create_puzzle_solution(&rules).unwrap();
+        let options = CreateOptions::new()
+            .symmetry(Symmetry::Rotational90)
+            .minimal(true);
+        create_puzzle_from_with_options(&mut board, &rules, &options).unwrap();
+        assert_eq!(is_minimal(&board, &rules).unwrap(), Minimality::Minimal);
+    }
+
+    #[test]
+    fn test_seeded_creation() {
+        let rules = ClassicSudoku {};
+        let solution: Board<81, 9, 3, 3> =
+            create_puzzle_solution_with_rng(&rules, &mut seeded_rng(1)).unwrap();
+        let mut puzzle = solution.clone();
+        let options = CreateOptions::new().seed(2);
+        create_puzzle_from_with_options(&mut puzzle, &rules, &options).unwrap();
+        // Fixed values catch changes to the random draws, which would break reproducibility.
+        assert_eq!(
+            format!("{:?}", solution),
+            "491628735738594216562317849614739528379852461825461973953246187286175394147983652"
+        );
+        assert_eq!(
+            format!("{:?}", puzzle),
+            "091000000000504006002307040000009008300002000005000900003200107080005004000080600"
+        );
+
+        let target = DifficultyTarget::Band(Band::Easy);
+        let (first, _): (Board<81, 9, 3, 3>, _) =
+            create_puzzle_with_difficulty(&rules, &target, 10, &options).unwrap();
+        let (second, _) = create_puzzle_with_difficulty(&rules, &target, 10, &options).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_create_puzzle_max_givens() {
+        let rules = ClassicSudoku {};
+        let options = CreateOptions::new().seed(3).max_givens(25);
+        let board: Board<81, 9, 3, 3> = create_puzzle(&rules, 100, &options).unwrap();
+        assert!(board.cells.iter().filter(|&&c| c != Cell::Unfilled).count() <= 25);
+
+        // Classic sudokus need at least 17 givens.
+        let options = CreateOptions::new().max_givens(16);
+        let result: Result<Board<81, 9, 3, 3>, _> = create_puzzle(&rules, 2, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::TooManyGivens(n)) if n > 16));
+
+        // The first order of removal leaves 25 givens but a later one gets down to 22.
+        let solution: Board<81, 9, 3, 3> =
+            create_puzzle_solution_with_rng(&rules, &mut seeded_rng(1)).unwrap();
+        let givens =
+            |b: &Board<81, 9, 3, 3>| b.cells.iter().filter(|&&c| c != Cell::Unfilled).count();
+        let mut board = solution.clone();
+        let options = CreateOptions::new().seed(101);
+        create_puzzle_from_with_options(&mut board, &rules, &options).unwrap();
+        assert_eq!(givens(&board), 25);
+        let mut board = solution.clone();
+        let options = options.max_givens(22);
+        create_puzzle_from_with_options(&mut board, &rules, &options).unwrap();
+        assert_eq!(givens(&board), 22);
+
+        // A failed creation leaves the board as it was.
+        let mut board = solution.clone();
+        let options = CreateOptions::new().seed(101).max_givens(16);
+        let result = create_puzzle_from_with_options(&mut board, &rules, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::TooManyGivens(_))));
+        assert_eq!(board, solution);
+    }
+
+    #[test]
+    fn test_create_puzzle_mask() {
+        let rules = ClassicSudoku {};
+        // Everything but the middle block.
+        let mask: Vec<usize> = (0..81)
+            .filter(|i| !((3..6).contains(&(i / 9)) && (3..6).contains(&(i % 9))))
+            .collect();
+        let options = CreateOptions::new().seed(4).mask(mask.iter().copied());
+        let board: Board<81, 9, 3, 3> = create_puzzle(&rules, 10, &options).unwrap();
+        for i in (0..81).filter(|i| !mask.contains(i)) {
+            assert!(board.cells[i] == Cell::Unfilled);
+        }
+
+        // Givens in the top row alone leave many solutions.
+        let options = CreateOptions::new().mask(0..9);
+        let result: Result<Board<81, 9, 3, 3>, _> = create_puzzle(&rules, 2, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::MaskNotUnique)));
+        let solution: Board<81, 9, 3, 3> = create_puzzle_solution(&rules).unwrap();
+        let mut board = solution.clone();
+        let result = create_puzzle_from_with_options(&mut board, &rules, &options);
+        assert!(matches!(result, Err(PuzzleCreateError::MaskNotUnique)));
+        assert_eq!(board, solution);
+
+        let options = CreateOptions::new().mask(vec![1000]);
+        let result: Result<Board<81, 9, 3, 3>, _> = create_puzzle(&rules, 2, &options);
+        assert!(matches!(
+            result,
+            Err(PuzzleCreateError::MaskOutOfRange(1000))
+        ));
+        let result = create_puzzle_from_with_options(&mut board, &rules, &options);
+        assert!(matches!(
+            result,
+            Err(PuzzleCreateError::MaskOutOfRange(1000))
+        ));
+        assert_eq!(board, solution);
+    }
+
+    #[test]
+    fn test_create_puzzle_no_attempts() {
+        let result: Result<Board<81, 9, 3, 3>, _> =
+            create_puzzle(&ClassicSudoku {}, 0, &CreateOptions::new());
+        assert!(matches!(result, Err(PuzzleCreateError::NoAttempts)));
+    }
 }
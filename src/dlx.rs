@@ -0,0 +1,263 @@
+use crate::solve::SolveResult;
+use crate::Board;
+use crate::Cell;
+use std::convert::TryInto;
+
+// Solves a board under the classic rules by reducing it to an exact cover problem
+// and running Knuth's Algorithm X with dancing links.
+pub fn solve_classic<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    solve_exact_cover(board, true)
+}
+
+// Same as solve_classic but only rows and columns must not contain duplicate digits.
+pub fn solve_irregular<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    solve_exact_cover(board, false)
+}
+
+fn solve_exact_cover<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    with_blocks: bool,
+) -> SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    // Every cell must hold a digit and every digit must appear once in each row, column, and block.
+    let num_columns = if with_blocks { 4 } else { 3 } * NUM_CELLS;
+    let mut matrix = Matrix::new(num_columns);
+    // Each matrix row places one digit in one cell and is identified by index * LENGTH + digit - 1.
+    let mut placements = Vec::new();
+    for (i, cell) in board.cells.iter().enumerate() {
+        let row = i / LENGTH;
+        let column = i % LENGTH;
+        let block = (row / BOX_HEIGHT) * (LENGTH / BOX_WIDTH) + column / BOX_WIDTH;
+        for digit in 0..LENGTH {
+            if let Cell::Filled(v) = cell {
+                if usize::from(v.get() - 1) != digit {
+                    continue;
+                }
+            }
+            let mut columns = vec![
+                i,
+                NUM_CELLS + row * LENGTH + digit,
+                2 * NUM_CELLS + column * LENGTH + digit,
+            ];
+            if with_blocks {
+                columns.push(3 * NUM_CELLS + block * LENGTH + digit);
+            }
+            matrix.add_row(placements.len(), &columns);
+            placements.push(i * LENGTH + digit);
+        }
+    }
+
+    let mut solutions = Vec::new();
+    matrix.search(&mut Vec::new(), &mut solutions, 2);
+    let to_board = |rows: &Vec<usize>| {
+        let mut solution = board.clone();
+        for &r in rows {
+            let placement = placements[r];
+            let digit: u8 = (placement % LENGTH + 1).try_into().unwrap();
+            solution.cells[placement / LENGTH] = Cell::Filled(digit.try_into().unwrap());
+        }
+        solution.reset_candidates();
+        solution
+    };
+    match solutions.len() {
+        0 => SolveResult::NoSolution,
+        1 => SolveResult::UniqueSolution(to_board(&solutions[0])),
+        _ => SolveResult::MultipleSolutions(to_board(&solutions[1])),
+    }
+}
+
+// Sparse 0-1 matrix as circular doubly linked lists. Node 0 is the root, nodes 1 to num_columns
+// are the column headers, and the remaining nodes are the ones in the matrix.
+struct Matrix {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl Matrix {
+    fn new(num_columns: usize) -> Self {
+        let headers = num_columns + 1;
+        Matrix {
+            left: (0..headers).map(|i| (i + headers - 1) % headers).collect(),
+            right: (0..headers).map(|i| (i + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![usize::MAX; headers],
+            size: vec![0; headers],
+        }
+    }
+
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.left.len();
+        for (n, &c) in columns.iter().enumerate() {
+            let header = c + 1;
+            let node = first + n;
+            self.left.push(if n == 0 {
+                first + columns.len() - 1
+            } else {
+                node - 1
+            });
+            self.right.push(if n == columns.len() - 1 {
+                first
+            } else {
+                node + 1
+            });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.row.push(row);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+    }
+
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    // Collects up to limit solutions, each as the list of chosen rows.
+    fn search(&mut self, chosen: &mut Vec<usize>, solutions: &mut Vec<Vec<usize>>, limit: usize) {
+        if self.right[0] == 0 {
+            solutions.push(chosen.clone());
+            return;
+        }
+        // Cover the column with the fewest ones first.
+        let mut header = self.right[0];
+        let mut c = self.right[header];
+        while c != 0 {
+            if self.size[c] < self.size[header] {
+                header = c;
+            }
+            c = self.right[c];
+        }
+        self.cover(header);
+        let mut r = self.down[header];
+        while r != header && solutions.len() < limit {
+            chosen.push(self.row[r]);
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+            self.search(chosen, solutions, limit);
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            chosen.pop();
+            r = self.down[r];
+        }
+        self.uncover(header);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::ClassicSudoku;
+    use crate::solve::solve;
+
+    #[test]
+    fn test_unique_solution() {
+        let puzzle: Board<81, 9, 3, 3> =
+            "000075400000000008080190000300001060000000034000068170204000603900000020530200000"
+                .parse()
+                .unwrap();
+        let solved: Board<81, 9, 3, 3> =
+            "693875412145632798782194356357421869816957234429368175274519683968743521531286947"
+                .parse()
+                .unwrap();
+        assert_eq!(solve_classic(&puzzle), SolveResult::UniqueSolution(solved));
+    }
+
+    #[test]
+    fn test_matches_backtracking() {
+        let puzzles = [
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+            "000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+            "110000000000000000000000000000000000000000000000000000000000000000000000000000000",
+        ];
+        for puzzle in puzzles.iter() {
+            let mut puzzle: Board<81, 9, 3, 3> = puzzle.parse().unwrap();
+            let expected = solve(&mut puzzle, &ClassicSudoku {});
+            let actual = solve_classic(&puzzle);
+            assert_eq!(
+                std::mem::discriminant(&expected),
+                std::mem::discriminant(&actual)
+            );
+            if let SolveResult::UniqueSolution(_) = expected {
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn test_irregular() {
+        // Without blocks, this Latin square has a second solution.
+        let puzzle: Board<16, 4, 2, 2> = "1200340000000000".parse().unwrap();
+        assert!(matches!(
+            solve_irregular(&puzzle),
+            SolveResult::MultipleSolutions(_)
+        ));
+        let puzzle: Board<16, 4, 2, 2> = "1234214334124321".parse().unwrap();
+        assert!(matches!(
+            solve_irregular(&puzzle),
+            SolveResult::UniqueSolution(_)
+        ));
+        assert!(matches!(solve_classic(&puzzle), SolveResult::NoSolution));
+    }
+}
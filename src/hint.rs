@@ -0,0 +1,160 @@
+use crate::logic::{next_step, with_candidates, Step};
+use crate::rules::PuzzleRules;
+use crate::solve::{solve, SolveResult};
+use crate::Board;
+use crate::Cell;
+use std::num::NonZeroU8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hint {
+    // Every cell is filled and the rules hold.
+    Solved,
+    // A filled cell holds a different digit than the unique solution. Conflicting is true if the
+    // entry breaks the rules together with the givens of the puzzle.
+    Mistake {
+        index: usize,
+        digit: NonZeroU8,
+        correct: NonZeroU8,
+        conflicting: bool,
+    },
+    // The easiest deduction which makes progress.
+    Deduction(Step),
+    // None of the techniques make progress, so the digit of a cell is taken from the solution.
+    Reveal {
+        index: usize,
+        digit: NonZeroU8,
+    },
+    // The puzzle has no unique solution to compare entries against.
+    NoUniqueSolution,
+}
+
+// Suggests what a player should do next on a board filled in from the given puzzle. Wrong entries
+// are reported first, preferring ones which visibly break the rules, then the easiest deduction.
+pub fn hint<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    puzzle: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> Hint {
+    let solution = match solve(&mut puzzle.clone(), rules) {
+        SolveResult::UniqueSolution(v) => v,
+        _ => return Hint::NoUniqueSolution,
+    };
+    let valid = rules.is_valid(board);
+    if valid && !board.cells.contains(&Cell::Unfilled) {
+        return Hint::Solved;
+    }
+
+    let mut mistake = None;
+    for i in 0..NUM_CELLS {
+        if let (Cell::Filled(digit), Cell::Filled(correct)) = (board.cells[i], solution.cells[i]) {
+            if digit != correct {
+                // Check the entry against the puzzle on its own to see if it conflicts.
+                let mut single = puzzle.clone();
+                single.cells[i] = Cell::Filled(digit);
+                let conflicting = !valid && !rules.is_valid(&single);
+                if mistake.is_none() || conflicting {
+                    mistake = Some(Hint::Mistake {
+                        index: i,
+                        digit,
+                        correct,
+                        conflicting,
+                    });
+                }
+                if conflicting {
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(mistake) = mistake {
+        return mistake;
+    }
+
+    let board = with_candidates(board, rules).unwrap();
+    if let Some(step) = next_step(&board) {
+        return Hint::Deduction(step);
+    }
+    let index = (0..NUM_CELLS)
+        .filter(|&i| board.cells[i] == Cell::Unfilled)
+        .min_by_key(|&i| board.candidates(i).len())
+        .unwrap();
+    match solution.cells[index] {
+        Cell::Filled(digit) => Hint::Reveal { index, digit },
+        Cell::Unfilled => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::Technique;
+    use crate::rules::ClassicSudoku;
+    use std::convert::TryInto;
+
+    const PUZZLE: &str =
+        "003020600900305001001806400008102900700000008006708200002609500800203009005010300";
+
+    #[test]
+    fn test_hint_deduction() {
+        let puzzle: Board<81, 9, 3, 3> = PUZZLE.parse().unwrap();
+        match hint(&puzzle, &puzzle, &ClassicSudoku {}) {
+            Hint::Deduction(step) => assert_eq!(step.technique, Technique::HiddenSingle),
+            other => panic!("unexpected hint {:?}", other),
+        }
+        let mut solution = puzzle.clone();
+        if let SolveResult::UniqueSolution(v) = solve(&mut solution, &ClassicSudoku {}) {
+            solution = v;
+        }
+        assert_eq!(hint(&puzzle, &solution, &ClassicSudoku {}), Hint::Solved);
+    }
+
+    #[test]
+    fn test_hint_mistake() {
+        let puzzle: Board<81, 9, 3, 3> = PUZZLE.parse().unwrap();
+        // The solution has 4 at r1c1. A 5 there does not break the rules yet.
+        let mut board = puzzle.clone();
+        board.cells[0] = Cell::Filled(5.try_into().unwrap());
+        assert_eq!(
+            hint(&puzzle, &board, &ClassicSudoku {}),
+            Hint::Mistake {
+                index: 0,
+                digit: 5.try_into().unwrap(),
+                correct: 4.try_into().unwrap(),
+                conflicting: false,
+            }
+        );
+        // A 3 in r1c2 repeats the 3 in r1c3 and is reported first.
+        board.cells[1] = Cell::Filled(3.try_into().unwrap());
+        assert!(matches!(
+            hint(&puzzle, &board, &ClassicSudoku {}),
+            Hint::Mistake {
+                index: 1,
+                conflicting: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_hint_reveal() {
+        // AI Escargot needs more than the available techniques.
+        let puzzle: Board<81, 9, 3, 3> =
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300"
+                .parse()
+                .unwrap();
+        assert!(matches!(
+            hint(&puzzle, &puzzle, &ClassicSudoku {}),
+            Hint::Deduction(_)
+        ));
+        let stuck = crate::logic::solve_logically(&puzzle, &ClassicSudoku {}).board;
+        assert!(matches!(
+            hint(&puzzle, &stuck, &ClassicSudoku {}),
+            Hint::Reveal { .. }
+        ));
+    }
+}
@@ -1,14 +1,23 @@
+pub mod candidates;
+pub mod cnf;
 pub mod create;
+pub mod dlx;
+pub mod hint;
+pub mod logic;
+pub mod parallel;
+pub mod rating;
 pub mod rules;
+pub mod search;
 pub mod solve;
+pub mod text;
 
-use std::convert::TryInto;
+use crate::candidates::Candidates;
 use std::error::Error;
 use std::fmt;
 use std::num::NonZeroU8;
 use std::str::FromStr;
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Board<
     const NUM_CELLS: usize,
     const LENGTH: usize,
@@ -17,6 +26,8 @@ pub struct Board<
 > {
     // Rows are read from left to right and then top to bottom.
     pub(crate) cells: [Cell; NUM_CELLS],
+    // Remaining digits for each cell. Only meaningful for unfilled cells.
+    pub(crate) candidates: [Candidates; NUM_CELLS],
 }
 
 impl<
@@ -27,12 +38,66 @@ impl<
     > Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
 {
     pub fn unfilled() -> Self {
-        Board {
-            cells: [Cell::Unfilled; NUM_CELLS],
+        Self::from_cells([Cell::Unfilled; NUM_CELLS])
+    }
+
+    // Fails to compile when used with parameters which do not describe a grid of whole boxes.
+    const VALID_SHAPE: () = assert!(
+        NUM_CELLS == LENGTH * LENGTH
+            && BOX_WIDTH * BOX_HEIGHT == LENGTH
+            && LENGTH > 0
+            && LENGTH <= 32,
+        "Board requires NUM_CELLS == LENGTH * LENGTH, BOX_WIDTH * BOX_HEIGHT == LENGTH and LENGTH <= 32"
+    );
+
+    pub(crate) fn from_cells(cells: [Cell; NUM_CELLS]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SHAPE;
+        let mut board = Board {
+            cells,
+            candidates: [Candidates::empty(); NUM_CELLS],
+        };
+        board.reset_candidates();
+        board
+    }
+
+    pub fn candidates(&self, index: usize) -> Candidates {
+        self.candidates[index]
+    }
+
+    // Returns whether the digit was a candidate before the removal.
+    pub fn remove_candidate(&mut self, index: usize, digit: NonZeroU8) -> bool {
+        self.candidates[index].remove(digit)
+    }
+
+    pub fn restore_candidate(&mut self, index: usize, digit: NonZeroU8) {
+        self.candidates[index].insert(digit);
+    }
+
+    // Filled cells get their own digit as the only candidate and unfilled cells get every digit.
+    pub fn reset_candidates(&mut self) {
+        for (candidates, cell) in self.candidates.iter_mut().zip(self.cells.iter()) {
+            *candidates = match cell {
+                Cell::Unfilled => Candidates::all(LENGTH),
+                Cell::Filled(v) => Candidates::single(*v),
+            };
         }
     }
 }
 
+// Boards are compared by their digits only. Candidates are working state for solvers.
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > PartialEq for Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.cells == other.cells
+    }
+}
+
 impl<
         const NUM_CELLS: usize,
         const LENGTH: usize,
@@ -42,26 +107,10 @@ impl<
 {
     type Err = ParseError;
 
+    // Reads letters or numbers, whichever the text looks like. See Notation for the characters
+    // which are skipped and the ones which mean blanks.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != NUM_CELLS {
-            return Err(ParseError {});
-        }
-        let mut cells = [Cell::Unfilled; NUM_CELLS];
-        for (dst, src) in cells.iter_mut().zip(s.chars().map(|c| {
-            let digit = match c.to_digit(10) {
-                None => return Err(ParseError {}),
-                Some(v) => v,
-            };
-            let digit: u8 = digit.try_into().unwrap();
-            let cell = match digit.try_into() {
-                Ok(v) => Cell::Filled(v),
-                Err(_) => Cell::Unfilled,
-            };
-            Ok(cell)
-        })) {
-            *dst = src?
-        }
-        Ok(Self { cells })
+        Self::parse(s, text::guess_notation(s, NUM_CELLS, LENGTH))
     }
 }
 
@@ -76,26 +125,117 @@ impl<
         for cell in self.cells.iter() {
             match cell {
                 Cell::Unfilled => f.write_str("0")?,
-                Cell::Filled(v) => v.fmt(f)?,
+                Cell::Filled(v) => fmt::Write::write_char(f, text::letter(v.get()))?,
             };
         }
         Ok(())
     }
 }
 
+// Draws the grid with lines between the boxes. See Board::render for other styles.
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > fmt::Display for Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&text::RenderOptions::new()))
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum Cell {
     Unfilled,
     Filled(NonZeroU8),
 }
 
-#[derive(Debug)]
-pub struct ParseError {}
+// Rows, columns and indexes are zero-based and displayed counting from one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    // The text holds a different number of cells than the board.
+    WrongLength {
+        expected: usize,
+        found: usize,
+    },
+    // A character which is neither a digit, a blank nor a drawing character. The index counts the
+    // characters of the text and the row and column are of the cell it was read for.
+    InvalidCharacter {
+        character: char,
+        index: usize,
+        row: usize,
+        column: usize,
+    },
+    // A digit larger than the length of the board, as it is written in the text.
+    DigitOutOfRange {
+        digit: String,
+        length: usize,
+        row: usize,
+        column: usize,
+    },
+}
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("Parse error")
+        match self {
+            ParseError::WrongLength { expected, found } => {
+                write!(f, "Expected {} cells but found {}", expected, found)
+            }
+            ParseError::InvalidCharacter {
+                character,
+                index,
+                row,
+                column,
+            } => write!(
+                f,
+                "Invalid character {:?} at position {} (row {}, column {})",
+                character,
+                index + 1,
+                row + 1,
+                column + 1
+            ),
+            ParseError::DigitOutOfRange {
+                digit,
+                length,
+                row,
+                column,
+            } => write!(
+                f,
+                "Digit {} at row {}, column {} is larger than {}",
+                digit,
+                row + 1,
+                column + 1,
+                length
+            ),
+        }
     }
 }
 
 impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let mut board: Board<16, 4, 2, 2> = "1000000000000000".parse().unwrap();
+        let one = NonZeroU8::new(1).unwrap();
+        let three = NonZeroU8::new(3).unwrap();
+        assert_eq!(board.candidates(0), Candidates::single(one));
+        assert_eq!(board.candidates(1), Candidates::all(4));
+        assert!(board.remove_candidate(1, three));
+        assert!(!board.remove_candidate(1, three));
+        assert_eq!(
+            board
+                .candidates(1)
+                .iter()
+                .map(|d| d.get())
+                .collect::<Vec<_>>(),
+            vec![1, 2, 4]
+        );
+        board.restore_candidate(1, three);
+        assert_eq!(board.candidates(1).len(), 4);
+    }
+}
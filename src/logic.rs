@@ -0,0 +1,1215 @@
+use crate::candidates::Candidates;
+use crate::rules::util::propagate_classic;
+use crate::rules::PuzzleRules;
+use crate::Board;
+use crate::Cell;
+use std::fmt;
+use std::num::NonZeroU8;
+
+pub mod explain;
+
+// Deductions a human solver can make without guessing, from easiest to hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    PointingPair,
+    BoxLineReduction,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    Skyscraper,
+    TwoStringKite,
+    XYWing,
+    XYZWing,
+    WWing,
+    SimpleColoring,
+    NakedQuad,
+    Jellyfish,
+    HiddenQuad,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 19] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::PointingPair,
+        Technique::BoxLineReduction,
+        Technique::NakedPair,
+        Technique::XWing,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::Swordfish,
+        Technique::HiddenTriple,
+        Technique::Skyscraper,
+        Technique::TwoStringKite,
+        Technique::XYWing,
+        Technique::XYZWing,
+        Technique::WWing,
+        Technique::SimpleColoring,
+        Technique::NakedQuad,
+        Technique::Jellyfish,
+        Technique::HiddenQuad,
+    ];
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Technique::HiddenSingle => "Hidden single",
+            Technique::NakedSingle => "Naked single",
+            Technique::PointingPair => "Pointing pair",
+            Technique::BoxLineReduction => "Box/line reduction",
+            Technique::NakedPair => "Naked pair",
+            Technique::XWing => "X-Wing",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::Swordfish => "Swordfish",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::Skyscraper => "Skyscraper",
+            Technique::TwoStringKite => "2-String Kite",
+            Technique::XYWing => "XY-Wing",
+            Technique::XYZWing => "XYZ-Wing",
+            Technique::WWing => "W-Wing",
+            Technique::SimpleColoring => "Simple coloring",
+            Technique::NakedQuad => "Naked quad",
+            Technique::Jellyfish => "Jellyfish",
+            Technique::HiddenQuad => "Hidden quad",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A row, column, or block, numbered from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Block(usize),
+}
+
+// A single deduction, why it holds, and the progress it makes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub technique: Technique,
+    // The cells, digits, and units the deduction is based on. See explain::explain for the
+    // order each technique lists them in.
+    pub cells: Vec<usize>,
+    pub digits: Vec<NonZeroU8>,
+    pub units: Vec<Unit>,
+    // Digits placed as (index, digit).
+    pub placements: Vec<(usize, NonZeroU8)>,
+    // Candidates removed as (index, digit).
+    pub eliminations: Vec<(usize, NonZeroU8)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogicStatus {
+    Solved,
+    // No technique makes progress. The board holds the digits and candidates at that point.
+    Stuck,
+    // The board breaks the rules or a deduction left a cell or digit without a place.
+    Invalid,
+}
+
+#[derive(Clone, Debug)]
+pub struct LogicResult<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+> {
+    pub status: LogicStatus,
+    pub board: Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    pub steps: Vec<Step>,
+}
+
+// Solves a board one deduction at a time, always using the easiest technique which makes progress.
+// The techniques reason about rows, columns, and blocks, so the rules must include the classic
+// constraints. Any other constraints only contribute through PuzzleRules::propagate.
+pub fn solve_logically<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> LogicResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    let mut steps = Vec::new();
+    let mut board = match with_candidates(board, rules) {
+        None => {
+            return LogicResult {
+                status: LogicStatus::Invalid,
+                board: board.clone(),
+                steps,
+            }
+        }
+        Some(v) => v,
+    };
+    let status = loop {
+        if !board.cells.contains(&Cell::Unfilled) {
+            break LogicStatus::Solved;
+        }
+        if has_contradiction(&board) {
+            break LogicStatus::Invalid;
+        }
+        let step = match next_step(&board) {
+            None => break LogicStatus::Stuck,
+            Some(v) => v,
+        };
+        let consistent = apply_step(&mut board, &step, rules);
+        steps.push(step);
+        if !consistent {
+            break LogicStatus::Invalid;
+        }
+    };
+    LogicResult {
+        status,
+        board,
+        steps,
+    }
+}
+
+// Copies the board with the candidates left by the rules after every digit on it is placed.
+// Returns None if the board breaks the rules.
+pub fn with_candidates<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
+    let mut board = board.clone();
+    board.reset_candidates();
+    if !rules.is_valid(&board) {
+        return None;
+    }
+    for i in 0..NUM_CELLS {
+        if board.cells[i] != Cell::Unfilled
+            && !(propagate_classic(&mut board, i) && rules.propagate(&mut board, i))
+        {
+            return None;
+        }
+    }
+    Some(board)
+}
+
+// The easiest deduction which makes progress on a board whose candidates are up to date.
+pub fn next_step<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> Option<Step> {
+    let grid = Grid::new(board);
+    Technique::ALL.iter().find_map(|&t| grid.find(t))
+}
+
+// Applies the placements and eliminations of a step. Returns false on a contradiction.
+pub fn apply_step<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    step: &Step,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> bool {
+    for &(i, d) in step.eliminations.iter() {
+        board.remove_candidate(i, d);
+    }
+    for &(i, d) in step.placements.iter() {
+        board.cells[i] = Cell::Filled(d);
+        board.candidates[i] = Candidates::single(d);
+        if !(rules.is_valid_at(board, i)
+            && propagate_classic(board, i)
+            && rules.propagate(board, i))
+        {
+            return false;
+        }
+    }
+    true
+}
+
+fn has_contradiction<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> bool {
+    let grid = Grid::new(board);
+    let empty_cell = (0..NUM_CELLS).any(|i| grid.is_unfilled(i) && grid.candidates(i).is_empty());
+    let missing_digit = grid.units.iter().any(|unit| {
+        let placed = unit
+            .iter()
+            .filter_map(|&i| match board.cells[i] {
+                Cell::Unfilled => None,
+                Cell::Filled(v) => Some(Candidates::single(v)),
+            })
+            .fold(Candidates::empty(), Candidates::union);
+        let possible = unit
+            .iter()
+            .map(|&i| grid.candidates(i))
+            .fold(placed, Candidates::union);
+        possible.len() < LENGTH
+    });
+    empty_cell || missing_digit
+}
+
+// A board with its rows, columns, and blocks, in that order, for finding deductions.
+struct Grid<
+    'a,
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+> {
+    board: &'a Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    units: Vec<Vec<usize>>,
+}
+
+impl<
+        'a,
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > Grid<'a, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    fn new(board: &'a Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> Self {
+        let mut units = Vec::new();
+        for row in 0..LENGTH {
+            units.push((0..LENGTH).map(|i| row * LENGTH + i).collect());
+        }
+        for column in 0..LENGTH {
+            units.push((0..LENGTH).map(|i| i * LENGTH + column).collect());
+        }
+        for block in 0..LENGTH {
+            let top = block / (LENGTH / BOX_WIDTH) * BOX_HEIGHT;
+            let left = block % (LENGTH / BOX_WIDTH) * BOX_WIDTH;
+            units.push(
+                (0..LENGTH)
+                    .map(|i| (top + i / BOX_WIDTH) * LENGTH + left + i % BOX_WIDTH)
+                    .collect(),
+            );
+        }
+        Grid { board, units }
+    }
+
+    fn find(&self, technique: Technique) -> Option<Step> {
+        match technique {
+            Technique::HiddenSingle => self.hidden_single(),
+            Technique::NakedSingle => self.naked_single(),
+            Technique::PointingPair => self.pointing(),
+            Technique::BoxLineReduction => self.box_line_reduction(),
+            Technique::NakedPair => self.naked_subset(2, technique),
+            Technique::NakedTriple => self.naked_subset(3, technique),
+            Technique::NakedQuad => self.naked_subset(4, technique),
+            Technique::HiddenPair => self.hidden_subset(2, technique),
+            Technique::HiddenTriple => self.hidden_subset(3, technique),
+            Technique::HiddenQuad => self.hidden_subset(4, technique),
+            Technique::XWing => self.fish(2, technique),
+            Technique::Swordfish => self.fish(3, technique),
+            Technique::Jellyfish => self.fish(4, technique),
+            Technique::Skyscraper => self.skyscraper(),
+            Technique::TwoStringKite => self.two_string_kite(),
+            Technique::XYWing => self.xy_wing(),
+            Technique::XYZWing => self.xyz_wing(),
+            Technique::WWing => self.w_wing(),
+            Technique::SimpleColoring => self.simple_coloring(),
+        }
+    }
+
+    fn blocks(&self) -> &[Vec<usize>] {
+        &self.units[2 * LENGTH..]
+    }
+
+    fn unit(&self, n: usize) -> Unit {
+        match n / LENGTH {
+            0 => Unit::Row(n),
+            1 => Unit::Column(n - LENGTH),
+            _ => Unit::Block(n - 2 * LENGTH),
+        }
+    }
+
+    // The row and column containing a cell, as indices into units.
+    fn row_of(&self, i: usize) -> usize {
+        i / LENGTH
+    }
+
+    fn column_of(&self, i: usize) -> usize {
+        LENGTH + i % LENGTH
+    }
+
+    fn is_unfilled(&self, i: usize) -> bool {
+        self.board.cells[i] == Cell::Unfilled
+    }
+
+    // The candidates of an unfilled cell, or no candidates for a filled one.
+    fn candidates(&self, i: usize) -> Candidates {
+        if self.is_unfilled(i) {
+            self.board.candidates(i)
+        } else {
+            Candidates::empty()
+        }
+    }
+
+    // The cells of a unit which may still hold the digit.
+    fn positions(&self, unit: &[usize], d: NonZeroU8) -> Vec<usize> {
+        unit.iter()
+            .copied()
+            .filter(|&i| self.candidates(i).contains(d))
+            .collect()
+    }
+
+    fn block_of(&self, i: usize) -> usize {
+        (i / LENGTH / BOX_HEIGHT) * (LENGTH / BOX_WIDTH) + i % LENGTH / BOX_WIDTH
+    }
+
+    // Whether two different cells share a row, column, or block.
+    fn sees(&self, a: usize, b: usize) -> bool {
+        a != b
+            && (a / LENGTH == b / LENGTH
+                || a % LENGTH == b % LENGTH
+                || self.block_of(a) == self.block_of(b))
+    }
+
+    // The candidates for the digit among the cells, skipping the excluded ones.
+    fn eliminate(
+        &self,
+        cells: impl Iterator<Item = usize>,
+        d: NonZeroU8,
+        excluded: &[usize],
+    ) -> Vec<(usize, NonZeroU8)> {
+        cells
+            .filter(|i| !excluded.contains(i) && self.candidates(*i).contains(d))
+            .map(|i| (i, d))
+            .collect()
+    }
+
+    // The candidates for the digit in every cell which sees all of the given cells.
+    fn eliminate_seen_by(&self, cells: &[usize], d: NonZeroU8) -> Vec<(usize, NonZeroU8)> {
+        self.eliminate(
+            (0..NUM_CELLS).filter(|&i| cells.iter().all(|&c| self.sees(i, c))),
+            d,
+            cells,
+        )
+    }
+
+    fn digits(&self) -> impl Iterator<Item = NonZeroU8> {
+        Candidates::all(LENGTH).iter()
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        // Blocks are usually the easiest place to spot one.
+        for n in (2 * LENGTH..3 * LENGTH).chain(0..2 * LENGTH) {
+            for d in self.digits() {
+                if let [i] = self.positions(&self.units[n], d)[..] {
+                    return Some(Step {
+                        technique: Technique::HiddenSingle,
+                        cells: vec![i],
+                        digits: vec![d],
+                        units: vec![self.unit(n)],
+                        placements: vec![(i, d)],
+                        eliminations: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        (0..NUM_CELLS)
+            .filter(|&i| self.candidates(i).len() == 1)
+            .map(|i| {
+                let d = self.candidates(i).iter().next().unwrap();
+                Step {
+                    technique: Technique::NakedSingle,
+                    cells: vec![i],
+                    digits: vec![d],
+                    units: Vec::new(),
+                    placements: vec![(i, d)],
+                    eliminations: Vec::new(),
+                }
+            })
+            .next()
+    }
+
+    fn pointing(&self) -> Option<Step> {
+        // A digit confined to one row or column of a block cannot appear elsewhere in that line.
+        for (n, block) in self.blocks().iter().enumerate() {
+            for d in self.digits() {
+                let positions = self.positions(block, d);
+                if positions.len() < 2 {
+                    continue;
+                }
+                for &l in [self.row_of(positions[0]), self.column_of(positions[0])].iter() {
+                    let line = &self.units[l];
+                    if positions.iter().all(|i| line.contains(i)) {
+                        let eliminations = self.eliminate(line.iter().copied(), d, block);
+                        if !eliminations.is_empty() {
+                            return Some(Step {
+                                technique: Technique::PointingPair,
+                                cells: positions,
+                                digits: vec![d],
+                                units: vec![Unit::Block(n), self.unit(l)],
+                                placements: Vec::new(),
+                                eliminations,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn box_line_reduction(&self) -> Option<Step> {
+        // A digit confined to one block within a line cannot appear elsewhere in that block.
+        for l in 0..2 * LENGTH {
+            let line = &self.units[l];
+            for d in self.digits() {
+                let positions = self.positions(line, d);
+                if positions.len() < 2 {
+                    continue;
+                }
+                let b = self.block_of(positions[0]);
+                let block = &self.blocks()[b];
+                if positions.iter().all(|i| block.contains(i)) {
+                    let eliminations = self.eliminate(block.iter().copied(), d, line);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::BoxLineReduction,
+                            cells: positions,
+                            digits: vec![d],
+                            units: vec![self.unit(l), Unit::Block(b)],
+                            placements: Vec::new(),
+                            eliminations,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_subset(&self, size: usize, technique: Technique) -> Option<Step> {
+        // N cells of a unit with only N candidates between them take those digits.
+        for (n, unit) in self.units.iter().enumerate() {
+            let cells: Vec<_> = unit
+                .iter()
+                .copied()
+                .filter(|&i| (2..=size).contains(&self.candidates(i).len()))
+                .collect();
+            for subset in combinations(&cells, size) {
+                let digits = subset
+                    .iter()
+                    .map(|&i| self.candidates(i))
+                    .fold(Candidates::empty(), Candidates::union);
+                if digits.len() != size {
+                    continue;
+                }
+                let eliminations: Vec<_> = digits
+                    .iter()
+                    .flat_map(|d| self.eliminate(unit.iter().copied(), d, &subset))
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique,
+                        cells: subset,
+                        digits: digits.iter().collect(),
+                        units: vec![self.unit(n)],
+                        placements: Vec::new(),
+                        eliminations,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn hidden_subset(&self, size: usize, technique: Technique) -> Option<Step> {
+        // N digits which fit in only N cells of a unit rule out every other digit in those cells.
+        for (n, unit) in self.units.iter().enumerate() {
+            let digits: Vec<_> = self
+                .digits()
+                .filter(|&d| (2..=size).contains(&self.positions(unit, d).len()))
+                .collect();
+            for subset in combinations(&digits, size) {
+                let mut cells: Vec<_> = subset
+                    .iter()
+                    .flat_map(|&d| self.positions(unit, d))
+                    .collect();
+                cells.sort_unstable();
+                cells.dedup();
+                if cells.len() != size {
+                    continue;
+                }
+                let kept = subset
+                    .iter()
+                    .map(|&d| Candidates::single(d))
+                    .fold(Candidates::empty(), Candidates::union);
+                let eliminations: Vec<_> = cells
+                    .iter()
+                    .flat_map(|&i| {
+                        self.candidates(i)
+                            .difference(kept)
+                            .iter()
+                            .map(move |d| (i, d))
+                    })
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique,
+                        cells,
+                        digits: subset,
+                        units: vec![self.unit(n)],
+                        placements: Vec::new(),
+                        eliminations,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn fish(&self, size: usize, technique: Technique) -> Option<Step> {
+        // If a digit's positions in N rows lie within N columns, those columns hold the digit in
+        // those rows, and the same with rows and columns swapped.
+        for &(base, cover) in [(0, LENGTH), (LENGTH, 0)].iter() {
+            let cover_of = |i: usize| {
+                if cover == 0 {
+                    self.row_of(i)
+                } else {
+                    self.column_of(i)
+                }
+            };
+            for d in self.digits() {
+                let lines: Vec<_> = (base..base + LENGTH)
+                    .filter(|&l| (2..=size).contains(&self.positions(&self.units[l], d).len()))
+                    .collect();
+                for subset in combinations(&lines, size) {
+                    let base_cells: Vec<_> = subset
+                        .iter()
+                        .flat_map(|&l| self.positions(&self.units[l], d))
+                        .collect();
+                    let mut cover_lines: Vec<_> = base_cells.iter().map(|&i| cover_of(i)).collect();
+                    cover_lines.sort_unstable();
+                    cover_lines.dedup();
+                    if cover_lines.len() != size {
+                        continue;
+                    }
+                    let excluded: Vec<_> = subset
+                        .iter()
+                        .flat_map(|&l| self.units[l].iter().copied())
+                        .collect();
+                    let eliminations: Vec<_> = cover_lines
+                        .iter()
+                        .flat_map(|&l| self.eliminate(self.units[l].iter().copied(), d, &excluded))
+                        .collect();
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique,
+                            cells: base_cells,
+                            digits: vec![d],
+                            units: subset
+                                .iter()
+                                .chain(cover_lines.iter())
+                                .map(|&l| self.unit(l))
+                                .collect(),
+                            placements: Vec::new(),
+                            eliminations,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Units where the digit has exactly two positions, as the unit and the pair of cells.
+    fn strong_links(
+        &self,
+        units: std::ops::Range<usize>,
+        d: NonZeroU8,
+    ) -> Vec<(usize, usize, usize)> {
+        units
+            .filter_map(|n| match self.positions(&self.units[n], d)[..] {
+                [a, b] => Some((n, a, b)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn skyscraper(&self) -> Option<Step> {
+        // Two parallel strong links joined at one end: one of the other two ends holds the digit.
+        for d in self.digits() {
+            for &(first, same_cover) in [
+                (
+                    0,
+                    (|a: usize, b: usize| a % LENGTH == b % LENGTH) as fn(usize, usize) -> bool,
+                ),
+                (
+                    LENGTH,
+                    (|a: usize, b: usize| a / LENGTH == b / LENGTH) as fn(usize, usize) -> bool,
+                ),
+            ]
+            .iter()
+            {
+                let links = self.strong_links(first..first + LENGTH, d);
+                for (n, &(line_a, a1, a2)) in links.iter().enumerate() {
+                    for &(line_b, b1, b2) in links[n + 1..].iter() {
+                        for &(base_a, roof_a, base_b, roof_b) in [
+                            (a1, a2, b1, b2),
+                            (a1, a2, b2, b1),
+                            (a2, a1, b1, b2),
+                            (a2, a1, b2, b1),
+                        ]
+                        .iter()
+                        {
+                            if !same_cover(base_a, base_b) || same_cover(roof_a, roof_b) {
+                                continue;
+                            }
+                            let eliminations = self.eliminate_seen_by(&[roof_a, roof_b], d);
+                            if !eliminations.is_empty() {
+                                return Some(Step {
+                                    technique: Technique::Skyscraper,
+                                    cells: vec![base_a, roof_a, base_b, roof_b],
+                                    digits: vec![d],
+                                    units: vec![self.unit(line_a), self.unit(line_b)],
+                                    placements: Vec::new(),
+                                    eliminations,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn two_string_kite(&self) -> Option<Step> {
+        // A row and a column strong link with one end of each in the same block.
+        for d in self.digits() {
+            let row_links = self.strong_links(0..LENGTH, d);
+            let column_links = self.strong_links(LENGTH..2 * LENGTH, d);
+            for &(row, r1, r2) in row_links.iter() {
+                for &(column, c1, c2) in column_links.iter() {
+                    for &(row_base, row_end, column_base, column_end) in [
+                        (r1, r2, c1, c2),
+                        (r1, r2, c2, c1),
+                        (r2, r1, c1, c2),
+                        (r2, r1, c2, c1),
+                    ]
+                    .iter()
+                    {
+                        let cells = [row_base, row_end, column_base, column_end];
+                        let distinct = (0..4).all(|i| (i + 1..4).all(|j| cells[i] != cells[j]));
+                        if !distinct || self.block_of(row_base) != self.block_of(column_base) {
+                            continue;
+                        }
+                        let eliminations = self.eliminate_seen_by(&[row_end, column_end], d);
+                        if !eliminations.is_empty() {
+                            return Some(Step {
+                                technique: Technique::TwoStringKite,
+                                cells: cells.to_vec(),
+                                digits: vec![d],
+                                units: vec![
+                                    self.unit(row),
+                                    self.unit(column),
+                                    Unit::Block(self.block_of(row_base)),
+                                ],
+                                placements: Vec::new(),
+                                eliminations,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+    fn bivalue_cells(&self) -> Vec<usize> {
+        (0..NUM_CELLS)
+            .filter(|&i| self.candidates(i).len() == 2)
+            .collect()
+    }
+
+    fn xy_wing(&self) -> Option<Step> {
+        // A pivot {x, y} seeing pincers {x, z} and {y, z}: one of the pincers holds z.
+        let bivalue = self.bivalue_cells();
+        for &pivot in bivalue.iter() {
+            let pivot_digits = self.candidates(pivot);
+            for &a in bivalue.iter().filter(|&&a| self.sees(pivot, a)) {
+                let shared = self.candidates(a).intersection(pivot_digits);
+                if shared.len() != 1 {
+                    continue;
+                }
+                let z = self.candidates(a).difference(shared);
+                let wanted = pivot_digits.difference(shared).union(z);
+                for &b in bivalue.iter() {
+                    if b == a || !self.sees(pivot, b) || self.candidates(b) != wanted {
+                        continue;
+                    }
+                    let x = shared.iter().next().unwrap();
+                    let y = pivot_digits.difference(shared).iter().next().unwrap();
+                    let z = z.iter().next().unwrap();
+                    let eliminations = self.eliminate_seen_by(&[a, b], z);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::XYWing,
+                            cells: vec![pivot, a, b],
+                            digits: vec![x, y, z],
+                            units: Vec::new(),
+                            placements: Vec::new(),
+                            eliminations,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn xyz_wing(&self) -> Option<Step> {
+        // A pivot {x, y, z} seeing pincers {x, z} and {y, z}: one of the three holds z.
+        let bivalue = self.bivalue_cells();
+        for pivot in (0..NUM_CELLS).filter(|&i| self.candidates(i).len() == 3) {
+            let pivot_digits = self.candidates(pivot);
+            let pincers: Vec<_> = bivalue
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    self.sees(pivot, i) && self.candidates(i).difference(pivot_digits).is_empty()
+                })
+                .collect();
+            for (n, &a) in pincers.iter().enumerate() {
+                for &b in pincers[n + 1..].iter() {
+                    let z = self.candidates(a).intersection(self.candidates(b));
+                    if z.len() != 1 || self.candidates(a).union(self.candidates(b)) != pivot_digits
+                    {
+                        continue;
+                    }
+                    let others = pivot_digits.difference(z);
+                    let x = self.candidates(a).difference(z).iter().next().unwrap();
+                    let y = others
+                        .difference(Candidates::single(x))
+                        .iter()
+                        .next()
+                        .unwrap();
+                    let z = z.iter().next().unwrap();
+                    let eliminations = self.eliminate_seen_by(&[pivot, a, b], z);
+                    if !eliminations.is_empty() {
+                        return Some(Step {
+                            technique: Technique::XYZWing,
+                            cells: vec![pivot, a, b],
+                            digits: vec![x, y, z],
+                            units: Vec::new(),
+                            placements: Vec::new(),
+                            eliminations,
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn w_wing(&self) -> Option<Step> {
+        // Two cells {x, y} joined by a strong link on x: one of them holds y.
+        let bivalue = self.bivalue_cells();
+        for (n, &a) in bivalue.iter().enumerate() {
+            for &b in bivalue[n + 1..].iter() {
+                let digits = self.candidates(a);
+                if self.candidates(b) != digits || self.sees(a, b) {
+                    continue;
+                }
+                for x in digits.iter() {
+                    let y = digits
+                        .difference(Candidates::single(x))
+                        .iter()
+                        .next()
+                        .unwrap();
+                    for (n, p, q) in self.strong_links(0..3 * LENGTH, x) {
+                        if [p, q].iter().any(|c| *c == a || *c == b) {
+                            continue;
+                        }
+                        let (p, q) = if self.sees(p, a) && self.sees(q, b) {
+                            (p, q)
+                        } else if self.sees(p, b) && self.sees(q, a) {
+                            (q, p)
+                        } else {
+                            continue;
+                        };
+                        let eliminations = self.eliminate_seen_by(&[a, b], y);
+                        if !eliminations.is_empty() {
+                            return Some(Step {
+                                technique: Technique::WWing,
+                                cells: vec![a, b, p, q],
+                                digits: vec![x, y],
+                                units: vec![self.unit(n)],
+                                placements: Vec::new(),
+                                eliminations,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn simple_coloring(&self) -> Option<Step> {
+        // Cells joined by strong links alternate between holding the digit and not.
+        for d in self.digits() {
+            let links = self.strong_links(0..3 * LENGTH, d);
+            let mut colors: Vec<Option<bool>> = vec![None; NUM_CELLS];
+            for &(_, start, _) in links.iter() {
+                if colors[start].is_some() {
+                    continue;
+                }
+                // Color the chain through this cell.
+                let mut chain = vec![start];
+                colors[start] = Some(true);
+                let mut consistent = true;
+                let mut n = 0;
+                while n < chain.len() {
+                    let cell = chain[n];
+                    let color = colors[cell].unwrap();
+                    for &(_, p, q) in links.iter() {
+                        let other = if p == cell {
+                            q
+                        } else if q == cell {
+                            p
+                        } else {
+                            continue;
+                        };
+                        match colors[other] {
+                            None => {
+                                colors[other] = Some(!color);
+                                chain.push(other);
+                            }
+                            Some(c) if c == color => consistent = false,
+                            Some(_) => (),
+                        }
+                    }
+                    n += 1;
+                }
+                if !consistent || chain.len() < 3 {
+                    continue;
+                }
+                let (on, off): (Vec<usize>, Vec<usize>) =
+                    chain.iter().partition(|&&i| colors[i] == Some(true));
+                let cells: Vec<_> = on.iter().chain(off.iter()).copied().collect();
+                // Two cells of the same color in one unit means that color is false.
+                for group in [&on, &off].iter() {
+                    let clash = group
+                        .iter()
+                        .any(|&a| group.iter().any(|&b| self.sees(a, b)));
+                    if clash {
+                        return Some(Step {
+                            technique: Technique::SimpleColoring,
+                            cells,
+                            digits: vec![d],
+                            units: Vec::new(),
+                            placements: Vec::new(),
+                            eliminations: group.iter().map(|&i| (i, d)).collect(),
+                        });
+                    }
+                }
+                // A cell which sees both colors cannot hold the digit.
+                let eliminations = self.eliminate(
+                    (0..NUM_CELLS).filter(|&i| {
+                        on.iter().any(|&a| self.sees(i, a)) && off.iter().any(|&b| self.sees(i, b))
+                    }),
+                    d,
+                    &chain,
+                );
+                if !eliminations.is_empty() {
+                    return Some(Step {
+                        technique: Technique::SimpleColoring,
+                        cells,
+                        digits: vec![d],
+                        units: Vec::new(),
+                        placements: Vec::new(),
+                        eliminations,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+// Every way to choose size items, keeping their order.
+fn combinations<T: Clone>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![Vec::new()];
+    }
+    let mut result = Vec::new();
+    for (n, item) in items.iter().enumerate() {
+        for mut rest in combinations(&items[n + 1..], size - 1) {
+            rest.insert(0, item.clone());
+            result.push(rest);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::ClassicSudoku;
+    use crate::solve::{solve, SolveResult};
+
+    // Checks every step against the unique solution.
+    fn assert_sound(puzzle: &str) -> LogicResult<81, 9, 3, 3> {
+        let mut board: Board<81, 9, 3, 3> = puzzle.parse().unwrap();
+        let solution = match solve(&mut board, &ClassicSudoku {}) {
+            SolveResult::UniqueSolution(v) => v,
+            _ => panic!("puzzle should have a unique solution"),
+        };
+        let result = solve_logically(&board, &ClassicSudoku {});
+        for step in result.steps.iter() {
+            for &(i, d) in step.placements.iter() {
+                assert!(solution.cells[i] == Cell::Filled(d), "{:?}", step);
+            }
+            for &(i, d) in step.eliminations.iter() {
+                assert!(solution.cells[i] != Cell::Filled(d), "{:?}", step);
+            }
+        }
+        result
+    }
+
+    #[test]
+    fn test_singles() {
+        let result = assert_sound(
+            "003020600900305001001806400008102900700000008006708200002609500800203009005010300",
+        );
+        assert_eq!(result.status, LogicStatus::Solved);
+        assert!(result
+            .steps
+            .iter()
+            .all(|s| s.technique <= Technique::NakedSingle));
+    }
+
+    #[test]
+    fn test_techniques_sound() {
+        // Each puzzle with the techniques its solution uses.
+        let puzzles: [(&str, &[Technique]); 4] = [
+            (
+                "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+                &[Technique::HiddenSingle],
+            ),
+            (
+                "000075400000000008080190000300001060000000034000068170204000603900000020530200000",
+                &[Technique::HiddenSingle, Technique::NakedSingle],
+            ),
+            (
+                "100000569492056108056109240009640801064010000218035604040500016905061402621000005",
+                &[
+                    Technique::HiddenSingle,
+                    Technique::PointingPair,
+                    Technique::XWing,
+                ],
+            ),
+            (
+                "900040000000600031020000090000700020002935600070002000060000073510009000000080009",
+                &[
+                    Technique::HiddenSingle,
+                    Technique::NakedSingle,
+                    Technique::PointingPair,
+                    Technique::BoxLineReduction,
+                    Technique::XYWing,
+                ],
+            ),
+        ];
+        for &(puzzle, techniques) in puzzles.iter() {
+            let result = assert_sound(puzzle);
+            assert_eq!(result.status, LogicStatus::Solved);
+            let mut used: Vec<_> = result.steps.iter().map(|s| s.technique).collect();
+            used.sort();
+            used.dedup();
+            assert_eq!(used, techniques);
+        }
+    }
+
+    // A technique, a puzzle, and the cells and eliminations (index, digit) of its first use.
+    type Case = (
+        Technique,
+        &'static str,
+        &'static [usize],
+        &'static [(usize, u8)],
+    );
+
+    #[test]
+    fn test_each_technique() {
+        // Every step is the easiest one available, so no simpler technique applies when each of
+        // these fires. assert_sound checks the eliminations against the solution.
+        let cases: [Case; 14] = [
+            (
+                Technique::PointingPair,
+                "100000569492056108056109240009640801064010000218035604040500016905061402621000005",
+                &[39, 48],
+                &[(75, 9)],
+            ),
+            (
+                Technique::NakedPair,
+                "008500020000020800000603004040005070000700000007009062510000000706408000080001003",
+                &[5, 14],
+                &[(41, 4), (59, 7)],
+            ),
+            (
+                Technique::HiddenPair,
+                "090000060006308000000100705000005039002000800600040000000700000520000001801000040",
+                &[33, 44],
+                &[(33, 1), (33, 2), (44, 7)],
+            ),
+            (
+                Technique::NakedTriple,
+                "130020000000000903000350000008000600500000308400500090809010000010006070000430200",
+                &[72, 73, 74],
+                &[(77, 5), (79, 5), (80, 5), (79, 6), (80, 6), (77, 7)],
+            ),
+            (
+                Technique::Swordfish,
+                "400900270300080000071000009000050800002009501000210030040000008005490000007106000",
+                &[29, 56, 21, 30, 57, 23, 32, 59],
+                &[(22, 3), (24, 3), (28, 3), (58, 3), (60, 3)],
+            ),
+            (
+                Technique::HiddenTriple,
+                "000004000103000000080000700200005010090000008035601900060100020000040689009030000",
+                &[4, 13, 22],
+                &[
+                    (4, 2),
+                    (4, 7),
+                    (4, 8),
+                    (4, 9),
+                    (13, 2),
+                    (13, 7),
+                    (13, 8),
+                    (13, 9),
+                    (22, 2),
+                    (22, 9),
+                ],
+            ),
+            (
+                Technique::Skyscraper,
+                "001000008090003050000009300050700000008001040003060090209004080000000070500100600",
+                &[74, 11, 77, 5],
+                &[(0, 7), (1, 7), (13, 7)],
+            ),
+            (
+                Technique::TwoStringKite,
+                "090000060006308000000100705000005039002000800600040000000700000520000001801000040",
+                &[70, 65, 80, 53],
+                &[(47, 7)],
+            ),
+            (
+                Technique::XYZWing,
+                "007001000000052008000400091030000005154600000906030000500240800060000040040300002",
+                &[1, 3, 19],
+                &[(0, 8)],
+            ),
+            (
+                Technique::WWing,
+                "000007002000028413002600000950700080000000000007053060046800700500400000000001000",
+                &[7, 12, 2, 11],
+                &[(3, 9), (4, 9)],
+            ),
+            (
+                Technique::SimpleColoring,
+                "000038600000501003001000002080719030000400005400003800009000000602000040100000520",
+                &[46, 3, 41, 58, 48, 13, 59],
+                &[(10, 2)],
+            ),
+            (
+                Technique::NakedQuad,
+                "000038600000501003001000002080719030000400005400003800009000000602000040100000520",
+                &[19, 55, 64, 73],
+                &[
+                    (37, 3),
+                    (1, 4),
+                    (10, 4),
+                    (1, 5),
+                    (1, 7),
+                    (10, 7),
+                    (37, 7),
+                    (46, 7),
+                ],
+            ),
+            (
+                Technique::Jellyfish,
+                "950600107403000090080090000000503000000000040010000906600280410001000000000005008",
+                &[10, 12, 14, 20, 21, 47, 48, 50, 55, 59],
+                &[
+                    (37, 7),
+                    (64, 7),
+                    (73, 7),
+                    (38, 7),
+                    (74, 7),
+                    (39, 7),
+                    (66, 7),
+                    (75, 7),
+                    (41, 7),
+                    (68, 7),
+                ],
+            ),
+            (
+                Technique::HiddenQuad,
+                "003500690008000120000601040005030700001208050090000200350400000109020000004009000",
+                &[60, 61, 62, 80],
+                &[(80, 3), (80, 5)],
+            ),
+        ];
+        for &(technique, puzzle, cells, eliminations) in cases.iter() {
+            let result = assert_sound(puzzle);
+            let step = result
+                .steps
+                .iter()
+                .find(|s| s.technique == technique)
+                .unwrap_or_else(|| panic!("{} should be used", technique));
+            assert_eq!(step.cells, cells, "{}", technique);
+            let expected: Vec<_> = eliminations
+                .iter()
+                .map(|&(i, d)| (i, NonZeroU8::new(d).unwrap()))
+                .collect();
+            assert_eq!(step.eliminations, expected, "{}", technique);
+        }
+    }
+
+    #[test]
+    fn test_stuck() {
+        // AI Escargot needs more than these techniques.
+        let result = assert_sound(
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+        );
+        assert_eq!(result.status, LogicStatus::Stuck);
+        assert!(result.board.cells.contains(&Cell::Unfilled));
+        assert_eq!(next_step(&result.board), None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let board: Board<81, 9, 3, 3> =
+            "110000000000000000000000000000000000000000000000000000000000000000000000000000000"
+                .parse()
+                .unwrap();
+        let result = solve_logically(&board, &ClassicSudoku {});
+        assert_eq!(result.status, LogicStatus::Invalid);
+    }
+}
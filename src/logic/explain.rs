@@ -0,0 +1,411 @@
+use crate::logic::{Step, Technique, Unit};
+use crate::{Board, Cell};
+use std::fmt;
+use std::num::NonZeroU8;
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(n) => write!(f, "row {}", n + 1),
+            Unit::Column(n) => write!(f, "column {}", n + 1),
+            Unit::Block(n) => write!(f, "block {}", n + 1),
+        }
+    }
+}
+
+// Describes a step in plain English, naming cells as r1c1 for the top left corner. The justifying
+// cells, digits, and units of each technique are listed in this order:
+// - Singles: the cell and digit placed, plus the unit for a hidden single.
+// - Pointing pair and box/line reduction: the digit's positions, the digit, then the unit which
+//   confines them followed by the unit they are eliminated from.
+// - Naked and hidden subsets: the cells, the digits, and the unit.
+// - X-Wing, Swordfish, and Jellyfish: the digit's positions, the digit, then the base lines
+//   followed by the same number of cover lines.
+// - Skyscraper: base and roof of the first link, base and roof of the second, the digit, and the
+//   two lines.
+// - 2-String Kite: the row link with its end in the shared block first, then the column link the
+//   same way, the digit, and the row, column, and shared block.
+// - XY-Wing and XYZ-Wing: the pivot and the pincers, and the digits x, y, z where the pincers hold
+//   {x, z} and {y, z}.
+// - W-Wing: the two bivalue cells, then the strong link ends they each see, the digits x, y where x
+//   is linked, and the unit of the link.
+// - Simple coloring: the chain with the cells of one color before the other, and the digit.
+// The board is the one the step was found on, or an earlier one, and sets the size the cells are
+// numbered by. Panics if the step does not fit it: a cell off the board, a placement in a filled
+// cell, or an elimination of a digit which is no longer a candidate.
+pub fn explain<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    step: &Step,
+) -> String {
+    assert!(
+        step.cells.iter().all(|&i| i < NUM_CELLS),
+        "step cells lie outside the board"
+    );
+    for &(i, _) in step.placements.iter() {
+        assert!(
+            board.cells.get(i) == Some(&Cell::Unfilled),
+            "step places a digit in r{}c{}, which is not an unfilled cell of the board",
+            i / LENGTH + 1,
+            i % LENGTH + 1
+        );
+    }
+    for &(i, d) in step.eliminations.iter() {
+        assert!(
+            board.candidates.get(i).map_or(false, |c| c.contains(d)),
+            "step removes {} from r{}c{}, which is not a candidate of the board",
+            d,
+            i / LENGTH + 1,
+            i % LENGTH + 1
+        );
+    }
+    let cell = |i: usize| format!("r{}c{}", i / LENGTH + 1, i % LENGTH + 1);
+    let cells = |cells: &[usize]| join(cells.iter().map(|&i| cell(i)).collect(), "and");
+    let digits = join(step.digits.iter().map(|d| d.to_string()).collect(), "and");
+    let units = |units: &[Unit]| join(units.iter().map(|u| u.to_string()).collect(), "and");
+    let c = &step.cells;
+    let d = &step.digits;
+    let u = &step.units;
+    let reason = match step.technique {
+        Technique::HiddenSingle => format!(
+            "{} is the only place for {} in {}",
+            cell(c[0]),
+            d[0],
+            u[0]
+        ),
+        Technique::NakedSingle => format!("{} is the only candidate left in {}", d[0], cell(c[0])),
+        Technique::PointingPair | Technique::BoxLineReduction => format!(
+            "in {}, {} can only go in {}, which all lie in {}",
+            u[0],
+            d[0],
+            cells(c),
+            u[1]
+        ),
+        Technique::NakedPair | Technique::NakedTriple | Technique::NakedQuad => format!(
+            "{} in {} only have the candidates {} between them",
+            cells(c),
+            u[0],
+            digits
+        ),
+        Technique::HiddenPair | Technique::HiddenTriple | Technique::HiddenQuad => format!(
+            "{} can only go in {} within {}",
+            digits,
+            cells(c),
+            u[0]
+        ),
+        Technique::XWing | Technique::Swordfish | Technique::Jellyfish => {
+            let (base, cover) = u.split_at(u.len() / 2);
+            format!(
+                "in {}, {} can only go in {}, which all lie in {}",
+                units(base),
+                d[0],
+                cells(c),
+                units(cover)
+            )
+        }
+        Technique::Skyscraper => format!(
+            "{} can only go in {} in {} and {} in {}, and since {} and {} line up, {} or {} must be {}",
+            d[0],
+            cells(&c[..2]),
+            u[0],
+            cells(&c[2..]),
+            u[1],
+            cell(c[0]),
+            cell(c[2]),
+            cell(c[1]),
+            cell(c[3]),
+            d[0]
+        ),
+        Technique::TwoStringKite => format!(
+            "{} can only go in {} in {} and {} in {}, and since {} and {} share {}, {} or {} must be {}",
+            d[0],
+            cells(&c[..2]),
+            u[0],
+            cells(&c[2..]),
+            u[1],
+            cell(c[0]),
+            cell(c[2]),
+            u[2],
+            cell(c[1]),
+            cell(c[3]),
+            d[0]
+        ),
+        Technique::XYWing => format!(
+            "since {} holds {} or {} and sees {} with {} or {} and {} with {} or {}, {} or {} must be {}",
+            cell(c[0]),
+            d[0],
+            d[1],
+            cell(c[1]),
+            d[0],
+            d[2],
+            cell(c[2]),
+            d[1],
+            d[2],
+            cell(c[1]),
+            cell(c[2]),
+            d[2]
+        ),
+        Technique::XYZWing => format!(
+            "since {} holds {}, {} or {} and sees {} with {} or {} and {} with {} or {}, one of them \
+             must be {}",
+            cell(c[0]),
+            d[0],
+            d[1],
+            d[2],
+            cell(c[1]),
+            d[0],
+            d[2],
+            cell(c[2]),
+            d[1],
+            d[2],
+            d[2]
+        ),
+        Technique::WWing => format!(
+            "since {} and {} both hold {} or {}, and {} in {} can only go in {} or {}, which see \
+             them, {} or {} must be {}",
+            cell(c[0]),
+            cell(c[1]),
+            d[0],
+            d[1],
+            d[0],
+            u[0],
+            cell(c[2]),
+            cell(c[3]),
+            cell(c[0]),
+            cell(c[1]),
+            d[1]
+        ),
+        Technique::SimpleColoring => format!(
+            "the strong links on {} through {} alternate between {} and not {}",
+            d[0],
+            cells(c),
+            d[0],
+            d[0]
+        ),
+    };
+
+    let mut effects = Vec::new();
+    for &(i, d) in step.placements.iter() {
+        effects.push(format!("{} must be {}", cell(i), d));
+    }
+    let mut removed: Vec<NonZeroU8> = step.eliminations.iter().map(|&(_, d)| d).collect();
+    removed.sort_unstable();
+    removed.dedup();
+    for d in removed {
+        let from: Vec<_> = step
+            .eliminations
+            .iter()
+            .filter(|&&(_, e)| e == d)
+            .map(|&(i, _)| i)
+            .collect();
+        effects.push(format!("{} can be removed from {}", d, cells(&from)));
+    }
+    format!(
+        "{}: {}, so {}.",
+        step.technique,
+        reason,
+        join(effects, "and")
+    )
+}
+
+// Joins items as "a, b and c".
+fn join(mut items: Vec<String>, conjunction: &str) -> String {
+    match items.len() {
+        0 => String::new(),
+        1 => items.remove(0),
+        _ => {
+            let last = items.pop().unwrap();
+            format!("{} {} {}", items.join(", "), conjunction, last)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::solve_logically;
+    use crate::rules::ClassicSudoku;
+    use std::convert::TryInto;
+
+    fn digit(d: u8) -> NonZeroU8 {
+        d.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_explain_single() {
+        let step = Step {
+            technique: Technique::HiddenSingle,
+            cells: vec![12],
+            digits: vec![digit(5)],
+            units: vec![Unit::Block(1)],
+            placements: vec![(12, digit(5))],
+            eliminations: Vec::new(),
+        };
+        assert_eq!(
+            explain(&Board::<81, 9, 3, 3>::unfilled(), &step),
+            "Hidden single: r2c4 is the only place for 5 in block 2, so r2c4 must be 5."
+        );
+    }
+
+    #[test]
+    fn test_explain_elimination() {
+        let step = Step {
+            technique: Technique::PointingPair,
+            cells: vec![0, 1],
+            digits: vec![digit(7)],
+            units: vec![Unit::Block(0), Unit::Row(0)],
+            placements: Vec::new(),
+            eliminations: vec![(4, digit(7)), (8, digit(7))],
+        };
+        assert_eq!(
+            explain(&Board::<81, 9, 3, 3>::unfilled(), &step),
+            "Pointing pair: in block 1, 7 can only go in r1c1 and r1c2, which all lie in row 1, \
+             so 7 can be removed from r1c5 and r1c9."
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not a candidate of the board")]
+    fn test_explain_other_board() {
+        let mut board = Board::<81, 9, 3, 3>::unfilled();
+        board.remove_candidate(1, digit(1));
+        let step = Step {
+            technique: Technique::PointingPair,
+            cells: vec![10, 11],
+            digits: vec![digit(1)],
+            units: vec![Unit::Block(0), Unit::Row(1)],
+            placements: Vec::new(),
+            eliminations: vec![(1, digit(1))],
+        };
+        explain(&board, &step);
+    }
+
+    #[test]
+    fn test_explain_all_steps() {
+        let board: Board<81, 9, 3, 3> =
+            "900040000000600031020000090000700020002935600070002000060000073510009000000080009"
+                .parse()
+                .unwrap();
+        let result = solve_logically(&board, &ClassicSudoku {});
+        assert!(result
+            .steps
+            .iter()
+            .any(|s| s.technique == Technique::XYWing));
+        for step in result.steps.iter() {
+            let text = explain(&board, step);
+            assert!(text.starts_with(&step.technique.to_string()));
+            assert!(text.ends_with('.'));
+        }
+    }
+
+    // The first step using the technique in the logical solution of the puzzle, explained.
+    fn explain_first(puzzle: &str, technique: Technique) -> String {
+        let board: Board<81, 9, 3, 3> = puzzle.parse().unwrap();
+        let result = solve_logically(&board, &ClassicSudoku {});
+        let step = result
+            .steps
+            .iter()
+            .find(|s| s.technique == technique)
+            .unwrap();
+        explain(&board, step)
+    }
+
+    #[test]
+    fn test_explain_templates() {
+        // A real step for each template, so the order of the cells, digits, and units matches the
+        // order logic.rs lists them in.
+        let cases = [
+            (
+                Technique::NakedSingle,
+                "000075400000000008080190000300001060000000034000068170204000603900000020530200000",
+                "Naked single: 5 is the only candidate left in r3c8, so r3c8 must be 5.",
+            ),
+            (
+                Technique::BoxLineReduction,
+                "900040000000600031020000090000700020002935600070002000060000073510009000000080009",
+                "Box/line reduction: in column 5, 1 can only go in r4c5 and r6c5, which all lie \
+                 in block 5, so 1 can be removed from r4c6 and r6c4.",
+            ),
+            (
+                Technique::NakedPair,
+                "008500020000020800000603004040005070000700000007009062510000000706408000080001003",
+                "Naked pair: r1c6 and r2c6 in column 6 only have the candidates 4 and 7 between \
+                 them, so 4 can be removed from r5c6 and 7 can be removed from r7c6.",
+            ),
+            (
+                Technique::HiddenPair,
+                "090000060006308000000100705000005039002000800600040000000700000520000001801000040",
+                "Hidden pair: 4 and 6 can only go in r4c7 and r5c9 within block 6, so 1 can be \
+                 removed from r4c7, 2 can be removed from r4c7 and 7 can be removed from r5c9.",
+            ),
+            (
+                Technique::XWing,
+                "100000569492056108056109240009640801064010000218035604040500016905061402621000005",
+                "X-Wing: in row 2 and row 6, 7 can only go in r2c4, r2c8, r6c4 and r6c8, which \
+                 all lie in column 4 and column 8, so 7 can be removed from r1c4, r5c4, r8c4, \
+                 r9c4, r4c8, r5c8, r8c8 and r9c8.",
+            ),
+            (
+                Technique::Swordfish,
+                "400900270300080000071000009000050800002009501000210030040000008005490000007106000",
+                "Swordfish: in column 3, column 4 and column 6, 3 can only go in r4c3, r7c3, \
+                 r3c4, r4c4, r7c4, r3c6, r4c6 and r7c6, which all lie in row 3, row 4 and row \
+                 7, so 3 can be removed from r3c5, r3c7, r4c2, r7c5 and r7c7.",
+            ),
+            (
+                Technique::Skyscraper,
+                "001000008090003050000009300050700000008001040003060090209004080000000070500100600",
+                "Skyscraper: 7 can only go in r9c3 and r2c3 in column 3 and r9c6 and r1c6 in \
+                 column 6, and since r9c3 and r9c6 line up, r2c3 or r1c6 must be 7, so 7 can be \
+                 removed from r1c1, r1c2 and r2c5.",
+            ),
+            (
+                Technique::TwoStringKite,
+                "090000060006308000000100705000005039002000800600040000000700000520000001801000040",
+                "2-String Kite: 7 can only go in r8c8 and r8c3 in row 8 and r9c9 and r6c9 in \
+                 column 9, and since r8c8 and r9c9 share block 9, r8c3 or r6c9 must be 7, so 7 \
+                 can be removed from r6c3.",
+            ),
+            (
+                Technique::XYWing,
+                "900040000000600031020000090000700020002935600070002000060000073510009000000080009",
+                "XY-Wing: since r1c2 holds 8 or 3 and sees r2c3 with 8 or 4 and r9c2 with 3 or \
+                 4, r2c3 or r9c2 must be 4, so 4 can be removed from r8c3.",
+            ),
+            (
+                Technique::XYZWing,
+                "007001000000052008000400091030000005154600000906030000500240800060000040040300002",
+                "XYZ-Wing: since r1c2 holds 9, 2 or 8 and sees r1c4 with 9 or 8 and r3c2 with 2 \
+                 or 8, one of them must be 8, so 8 can be removed from r1c1.",
+            ),
+            (
+                Technique::WWing,
+                "000007002000028413002600000950700080000000000007053060046800700500400000000001000",
+                "W-Wing: since r1c8 and r2c4 both hold 5 or 9, and 5 in column 3 can only go in \
+                 r1c3 or r2c3, which see them, r1c8 or r2c4 must be 9, so 9 can be removed from \
+                 r1c4 and r1c5.",
+            ),
+            (
+                Technique::SimpleColoring,
+                "000038600000501003001000002080719030000400005400003800009000000602000040100000520",
+                "Simple coloring: the strong links on 2 through r6c2, r1c4, r5c6, r7c5, r6c4, \
+                 r2c5 and r7c6 alternate between 2 and not 2, so 2 can be removed from r2c2.",
+            ),
+            (
+                Technique::Jellyfish,
+                "950600107403000090080090000000503000000000040010000906600280410001000000000005008",
+                "Jellyfish: in row 2, row 3, row 6 and row 7, 7 can only go in r2c2, r2c4, \
+                 r2c6, r3c3, r3c4, r6c3, r6c4, r6c6, r7c2 and r7c6, which all lie in column 2, \
+                 column 3, column 4 and column 6, so 7 can be removed from r5c2, r8c2, r9c2, \
+                 r5c3, r9c3, r5c4, r8c4, r9c4, r5c6 and r8c6.",
+            ),
+        ];
+        for &(technique, puzzle, expected) in cases.iter() {
+            assert_eq!(explain_first(puzzle, technique), expected);
+        }
+    }
+}
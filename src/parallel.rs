@@ -0,0 +1,216 @@
+use crate::rules::PuzzleRules;
+use crate::search::{Search, SearchBudget};
+use crate::solve::solutions_with_budget;
+use crate::solve::SolveResult;
+use crate::Board;
+use crate::Cell;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Same as solve::solve but searches separate parts of the search tree on separate threads.
+pub fn par_solve<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &(impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> + Sync),
+) -> SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    let found = Mutex::new(Vec::new());
+    search_parallel(board, rules, |solution| {
+        let mut found = found.lock().unwrap();
+        found.push(solution);
+        // A second solution settles the result.
+        found.len() >= 2
+    });
+    let mut found = found.into_inner().unwrap().into_iter();
+    match (found.next(), found.next()) {
+        (None, _) => SolveResult::NoSolution,
+        (Some(b), None) => SolveResult::UniqueSolution(b),
+        (Some(_), Some(b)) => SolveResult::MultipleSolutions(b),
+    }
+}
+
+// Same as solve::solve_one but returns whichever solution a thread finds first.
+pub fn par_solve_one<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &(impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> + Sync),
+) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
+    let found = Mutex::new(None);
+    search_parallel(board, rules, |solution| {
+        found.lock().unwrap().get_or_insert(solution);
+        true
+    });
+    found.into_inner().unwrap()
+}
+
+// Same as solve::derive. Stops once the solutions agree on no digit beyond those already on the board.
+pub fn par_derive<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &(impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> + Sync),
+) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
+    let acc: Mutex<Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>> = Mutex::new(None);
+    search_parallel(board, rules, |solution| {
+        let mut acc = acc.lock().unwrap();
+        let acc = acc.get_or_insert(solution.clone());
+        for (dst, src) in acc.cells.iter_mut().zip(solution.cells.iter()) {
+            if *dst != *src {
+                *dst = Cell::Unfilled;
+            }
+        }
+        *acc == *board
+    });
+    let mut acc = acc.into_inner().unwrap()?;
+    acc.reset_candidates();
+    Some(acc)
+}
+
+// Splits the search into subtrees, hands them to a pool of threads and reports every solution
+// found. Once the callback returns true, all threads stop as soon as possible.
+fn search_parallel<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &(impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> + Sync),
+    on_solution: impl Fn(Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> bool + Sync,
+) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let budget = SearchBudget::unlimited().cancel_flag(cancel.clone());
+    let search_branch = |branch: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>| {
+        for solution in solutions_with_budget(branch, rules, &budget) {
+            if on_solution(solution) {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        }
+    };
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    // Subtrees vary widely in size, so each thread gets several to even out the work.
+    let branches = split(board, rules, cores * 4);
+    let num_threads = cores.min(branches.len());
+    let next_branch = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..num_threads {
+            scope.spawn(|| loop {
+                let i = next_branch.fetch_add(1, Ordering::Relaxed);
+                if i >= branches.len() || cancel.load(Ordering::Relaxed) {
+                    break;
+                }
+                search_branch(&branches[i]);
+            });
+        }
+    });
+}
+
+// Boards which together cover the search of the given board, split one branching cell at a time
+// until there are at least the given number or none can be split further. Forced cells only add
+// a digit to a board, so the split goes past them to the first real guess.
+fn split<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    count: usize,
+) -> Vec<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
+    let mut boards = vec![board.clone()];
+    loop {
+        if boards.len() >= count {
+            return boards;
+        }
+        let mut split = false;
+        let mut next = Vec::new();
+        for board in boards {
+            match Search::new(&board, rules).branches() {
+                None => next.push(board),
+                Some(branches) => {
+                    split = true;
+                    next.extend(branches);
+                }
+            }
+        }
+        boards = next;
+        if !split {
+            return boards;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{ClassicSudoku, KnightsRestrictionSudoku, Miracle};
+    use crate::solve::{derive, solutions, solve};
+
+    #[test]
+    fn test_par_solve() {
+        let mut puzzle: Board<81, 9, 3, 3> =
+            "000075400000000008080190000300001060000000034000068170204000603900000020530200000"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            par_solve(&puzzle, &ClassicSudoku {}),
+            solve(&mut puzzle, &ClassicSudoku {})
+        );
+        let empty: Board<81, 9, 3, 3> = Board::unfilled();
+        assert!(matches!(
+            par_solve(&empty, &ClassicSudoku {}),
+            SolveResult::MultipleSolutions(_)
+        ));
+        let solution = par_solve_one(&empty, &Miracle {}).unwrap();
+        assert!(Miracle {}.is_valid(&solution));
+    }
+
+    #[test]
+    fn test_par_derive() {
+        let mut missing: Board<81, 9, 3, 3> =
+            "853971624946823157127654983312549768465387219789162005298715006634298571571436892"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            par_derive(&missing, &KnightsRestrictionSudoku {}),
+            derive(&mut missing, &KnightsRestrictionSudoku {})
+        );
+        let empty: Board<16, 4, 2, 2> = Board::unfilled();
+        assert_eq!(par_derive(&empty, &ClassicSudoku {}), Some(empty.clone()));
+    }
+
+    #[test]
+    fn test_split() {
+        let puzzle: Board<81, 9, 3, 3> =
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300"
+                .parse()
+                .unwrap();
+        let rules = ClassicSudoku {};
+        // The search of this puzzle starts with a forced cell, so its first frame cannot be split.
+        assert_eq!(Search::new(&puzzle, &rules).branches().unwrap().len(), 1);
+        let branches = split(&puzzle, &rules, 4);
+        assert!(branches.len() >= 4);
+        // Together the parts hold the same solutions as the whole search.
+        let found: Vec<_> = branches
+            .iter()
+            .flat_map(|b| solutions(b, &rules).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(found, solutions(&puzzle, &rules).collect::<Vec<_>>());
+
+        // A solved board cannot be split.
+        let solved = found[0].clone();
+        assert_eq!(split(&solved, &rules, 4), vec![solved]);
+    }
+}
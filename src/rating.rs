@@ -0,0 +1,157 @@
+use crate::logic::{solve_logically, LogicStatus, Step, Technique, Unit};
+use crate::rules::PuzzleRules;
+use crate::solve::{solve, SolveResult};
+use crate::Board;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Band {
+    Easy,
+    Medium,
+    Hard,
+    Fiendish,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    // Follows the Sudoku Explainer scale, from 1.2 for a puzzle of hidden singles upwards.
+    pub score: f32,
+    pub band: Band,
+    // None if the puzzle is already solved.
+    pub hardest: Option<Technique>,
+    pub steps: usize,
+    // False if the techniques could not finish the puzzle, which is then rated BEYOND_TECHNIQUES.
+    pub solved: bool,
+}
+
+// The score of a puzzle which needs more than the techniques of the logic module, such as chains.
+pub const BEYOND_TECHNIQUES: f32 = 6.0;
+
+// Rates a puzzle by solving it logically. Like Sudoku Explainer, the score is the rating of the
+// hardest step alone. The number of steps is reported separately. Returns None unless the puzzle
+// has exactly one solution.
+pub fn rate<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+) -> Option<Rating> {
+    match solve(&mut board.clone(), rules) {
+        SolveResult::UniqueSolution(_) => {}
+        SolveResult::NoSolution | SolveResult::MultipleSolutions(_) => return None,
+    }
+    let result = solve_logically(board, rules);
+    let steps = result.steps.len();
+    let hardest_step = result
+        .steps
+        .iter()
+        .max_by(|a, b| step_score(a).partial_cmp(&step_score(b)).unwrap());
+    let hardest = hardest_step.map(|s| s.technique);
+    let score = match result.status {
+        LogicStatus::Invalid => return None,
+        LogicStatus::Stuck => BEYOND_TECHNIQUES,
+        LogicStatus::Solved => hardest_step.map_or(0.0, step_score),
+    };
+    Some(Rating {
+        score,
+        band: band(score),
+        hardest,
+        steps,
+        solved: result.status == LogicStatus::Solved,
+    })
+}
+
+// The Sudoku Explainer rating of a single step. Techniques which it does not rate are placed
+// between the ones of similar difficulty.
+pub fn step_score(step: &Step) -> f32 {
+    match step.technique {
+        Technique::HiddenSingle => match step.units.first() {
+            Some(Unit::Block(_)) => 1.2,
+            _ => 1.5,
+        },
+        Technique::NakedSingle => 2.3,
+        Technique::PointingPair => 2.6,
+        Technique::BoxLineReduction => 2.8,
+        Technique::NakedPair => 3.0,
+        Technique::XWing => 3.2,
+        Technique::HiddenPair => 3.4,
+        Technique::NakedTriple => 3.6,
+        Technique::Swordfish => 3.8,
+        Technique::HiddenTriple => 4.0,
+        Technique::Skyscraper => 4.0,
+        Technique::TwoStringKite => 4.1,
+        Technique::XYWing => 4.2,
+        Technique::XYZWing => 4.4,
+        Technique::WWing => 4.4,
+        Technique::SimpleColoring => 4.5,
+        Technique::NakedQuad => 5.0,
+        Technique::Jellyfish => 5.2,
+        Technique::HiddenQuad => 5.4,
+    }
+}
+
+pub fn band(score: f32) -> Band {
+    if score <= 2.3 {
+        Band::Easy
+    } else if score <= 3.4 {
+        Band::Medium
+    } else if score <= 4.6 {
+        Band::Hard
+    } else {
+        Band::Fiendish
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::ClassicSudoku;
+
+    fn rate_classic(puzzle: &str) -> Rating {
+        let board: Board<81, 9, 3, 3> = puzzle.parse().unwrap();
+        rate(&board, &ClassicSudoku {}).unwrap()
+    }
+
+    #[test]
+    fn test_rate_bands() {
+        let easy = rate_classic(
+            "003020600900305001001806400008102900700000008006708200002609500800203009005010300",
+        );
+        assert_eq!(easy.band, Band::Easy);
+        assert!(easy.score <= 2.3);
+        assert!(easy.solved);
+
+        let hard = rate_classic(
+            "900040000000600031020000090000700020002935600070002000060000073510009000000080009",
+        );
+        assert_eq!(hard.hardest, Some(Technique::XYWing));
+        // Only the hardest step counts, however often it is needed.
+        assert_eq!(hard.score, 4.2);
+        assert_eq!(hard.band, Band::Hard);
+        assert!(hard.score > easy.score);
+    }
+
+    #[test]
+    fn test_rate_beyond() {
+        // AI Escargot.
+        let rating = rate_classic(
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+        );
+        assert!(!rating.solved);
+        assert_eq!(rating.score, BEYOND_TECHNIQUES);
+        assert_eq!(rating.band, Band::Fiendish);
+    }
+
+    #[test]
+    fn test_rate_not_unique() {
+        let rules = ClassicSudoku {};
+        assert_eq!(rate(&Board::<81, 9, 3, 3>::unfilled(), &rules), None);
+        let board: Board<81, 9, 3, 3> =
+            "110000000000000000000000000000000000000000000000000000000000000000000000000000000"
+                .parse()
+                .unwrap();
+        assert_eq!(rate(&board, &rules), None);
+    }
+}
@@ -3,11 +3,15 @@ pub mod util;
 use crate::Board;
 use crate::Cell;
 use crate::ParseError;
+use std::convert::TryInto;
 use std::str::FromStr;
 
 use self::util::{
-    is_valid_classic, passes_kings_move_constraint, passes_knights_move_constraint,
-    passes_nonconsecutive_constraint,
+    classic_units, eliminate, is_valid_classic, is_valid_classic_at, offset,
+    passes_kings_move_constraint, passes_kings_move_constraint_at, passes_knights_move_constraint,
+    passes_knights_move_constraint_at, passes_nonconsecutive_constraint,
+    passes_nonconsecutive_constraint_at, propagate_classic, propagate_kings_move,
+    propagate_knights_move, propagate_nonconsecutive, ORTHOGONAL_MOVES,
 };
 
 pub struct Miracle {}
@@ -25,6 +29,32 @@ impl<
             && passes_kings_move_constraint(board)
             && passes_nonconsecutive_constraint(board)
     }
+
+    fn is_valid_at(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        is_valid_classic_at(board, index)
+            && passes_knights_move_constraint_at(board, index)
+            && passes_kings_move_constraint_at(board, index)
+            && passes_nonconsecutive_constraint_at(board, index)
+    }
+
+    fn propagate(
+        &self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        propagate_classic(board, index)
+            && propagate_knights_move(board, index)
+            && propagate_kings_move(board, index)
+            && propagate_nonconsecutive(board, index)
+    }
+
+    fn units(&self) -> Vec<Vec<usize>> {
+        classic_units::<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>()
+    }
 }
 
 pub trait PuzzleRules<
@@ -35,6 +65,33 @@ pub trait PuzzleRules<
 >
 {
     fn is_valid(&self, board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> bool;
+
+    // Checks a board which was valid before the cell at the index changed.
+    // Rules can override this to only look at the cells affected by the change.
+    fn is_valid_at(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        _index: usize,
+    ) -> bool {
+        self.is_valid(board)
+    }
+
+    // Called after a digit is placed at the index. Removes the candidates of other cells which the
+    // placement rules out and returns false if some unfilled cell is left without candidates.
+    // Rules which only implement is_valid can rely on the default, which removes nothing.
+    fn propagate(
+        &self,
+        _board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        _index: usize,
+    ) -> bool {
+        true
+    }
+
+    // Groups of cells which must hold every digit exactly once. The search places a digit with a
+    // single cell left in one of them. Rules can leave this empty.
+    fn units(&self) -> Vec<Vec<usize>> {
+        Vec::new()
+    }
 }
 pub struct ClassicSudoku {}
 
@@ -48,6 +105,26 @@ impl<
     fn is_valid(&self, board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> bool {
         is_valid_classic(board)
     }
+
+    fn is_valid_at(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        is_valid_classic_at(board, index)
+    }
+
+    fn propagate(
+        &self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        propagate_classic(board, index)
+    }
+
+    fn units(&self) -> Vec<Vec<usize>> {
+        classic_units::<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>()
+    }
 }
 
 pub struct KnightsRestrictionSudoku {}
@@ -62,6 +139,26 @@ impl<
     fn is_valid(&self, board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> bool {
         passes_knights_move_constraint(board) && is_valid_classic(board)
     }
+
+    fn is_valid_at(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        passes_knights_move_constraint_at(board, index) && is_valid_classic_at(board, index)
+    }
+
+    fn propagate(
+        &self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        propagate_knights_move(board, index) && propagate_classic(board, index)
+    }
+
+    fn units(&self) -> Vec<Vec<usize>> {
+        classic_units::<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>()
+    }
 }
 
 // A very uninteresting puzzle constraint.
@@ -74,7 +171,7 @@ pub struct ParityMask<
     const BOX_WIDTH: usize,
     const BOX_HEIGHT: usize,
 > {
-    mask: Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    pub(crate) mask: Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
 }
 
 impl<
@@ -114,6 +211,32 @@ impl<
         }
         is_valid_classic(board)
     }
+
+    fn is_valid_at(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        match (board.cells[index], self.mask.cells[index]) {
+            (Cell::Filled(v1), Cell::Filled(v2)) if v1.get() % 2 != v2.get() % 2 => false,
+            (_, _) => is_valid_classic_at(board, index),
+        }
+    }
+
+    fn propagate(
+        &self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        match (board.cells[index], self.mask.cells[index]) {
+            (Cell::Filled(v1), Cell::Filled(v2)) if v1.get() % 2 != v2.get() % 2 => false,
+            (_, _) => propagate_classic(board, index),
+        }
+    }
+
+    fn units(&self) -> Vec<Vec<usize>> {
+        classic_units::<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>()
+    }
 }
 
 // Even digits must have odd orthogonally adjacent cells.
@@ -151,6 +274,52 @@ impl<
         }
         is_valid_classic(board)
     }
+
+    fn is_valid_at(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        let has_even_neighbor = match board.cells[index] {
+            Cell::Filled(v) if v.get() % 2 == 0 => ORTHOGONAL_MOVES.iter().any(|&(dr, dc)| {
+                matches!(
+                    offset::<LENGTH>(index, dr, dc).map(|i| board.cells[i]),
+                    Some(Cell::Filled(n)) if n.get() % 2 == 0
+                )
+            }),
+            _ => false,
+        };
+        !has_even_neighbor && is_valid_classic_at(board, index)
+    }
+
+    fn propagate(
+        &self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        // An even digit rules out every even digit in its orthogonally adjacent cells.
+        if let Cell::Filled(v) = board.cells[index] {
+            if v.get() % 2 == 0 {
+                for &(dr, dc) in ORTHOGONAL_MOVES.iter() {
+                    let neighbor = match offset::<LENGTH>(index, dr, dc) {
+                        None => continue,
+                        Some(i) => i,
+                    };
+                    for even in (2..=LENGTH).step_by(2) {
+                        let even: u8 = even.try_into().unwrap();
+                        if !eliminate(board, neighbor, even.try_into().unwrap()) {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        propagate_classic(board, index)
+    }
+
+    fn units(&self) -> Vec<Vec<usize>> {
+        classic_units::<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>()
+    }
 }
 
 #[cfg(test)]
@@ -158,7 +327,6 @@ mod tests {
     use super::*;
     use crate::solve::solve;
     use crate::solve::SolveResult;
-    use std::convert::TryInto;
 
     #[test]
     fn test_even_odd_valid() {
@@ -166,9 +334,8 @@ mod tests {
             "132547698547698123698123574321456789874931256965872341419765832783214965256389417"
                 .parse()
                 .unwrap();
-        let invalid: Board<81, 9, 3, 3> = Board {
-            cells: [Cell::Filled(1.try_into().unwrap()); 81],
-        };
+        let invalid: Board<81, 9, 3, 3> =
+            Board::from_cells([Cell::Filled(1.try_into().unwrap()); 81]);
         let rule = EvenOddNeighbors {};
         assert!(rule.is_valid(&board));
         assert!(!rule.is_valid(&invalid));
@@ -201,9 +368,7 @@ mod tests {
                 .parse()
                 .unwrap();
         let empty = Board::unfilled();
-        let invalid = Board {
-            cells: [Cell::Filled(1.try_into().unwrap()); 81],
-        };
+        let invalid = Board::from_cells([Cell::Filled(1.try_into().unwrap()); 81]);
         let mask: ParityMask<81, 9, 3, 3> =
             "112121212121212121212121112121212121212111212121212121211121212121212121212121211"
                 .parse()
@@ -213,4 +378,95 @@ mod tests {
         assert!(mask.is_valid(&empty));
         assert!(!mask.is_valid(&invalid));
     }
+
+    #[test]
+    fn test_miracle_propagate() {
+        let mut board: Board<81, 9, 3, 3> = Board::unfilled();
+        let five = 5.try_into().unwrap();
+        board.cells[40] = Cell::Filled(five);
+        assert!(Miracle {}.propagate(&mut board, 40));
+        // Same row, knight's move, king's move and unrelated cells.
+        assert!(!board.candidates(36).contains(five));
+        assert!(!board.candidates(21).contains(five));
+        assert!(!board.candidates(30).contains(five));
+        assert!(board.candidates(0).contains(five));
+        // Orthogonal neighbors cannot be consecutive.
+        assert_eq!(
+            board
+                .candidates(41)
+                .iter()
+                .map(|d| d.get())
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 7, 8, 9]
+        );
+
+        // A cell left without candidates is a contradiction.
+        let mut board: Board<81, 9, 3, 3> = Board::unfilled();
+        for d in 2..=9 {
+            board.remove_candidate(1, d.try_into().unwrap());
+        }
+        board.cells[0] = Cell::Filled(1.try_into().unwrap());
+        assert!(!ClassicSudoku {}.propagate(&mut board, 0));
+    }
+
+    #[test]
+    fn test_miracle_valid_at() {
+        let board: Board<81, 9, 3, 3> =
+            "147582936582936471936471825471825369825369714369714258714258693258693147693147582"
+                .parse()
+                .unwrap();
+        let rule = Miracle {};
+        assert!((0..81).all(|i| rule.is_valid_at(&board, i)));
+
+        // Consecutive orthogonal neighbors are only caught by the nonconsecutive constraint.
+        let mut board: Board<81, 9, 3, 3> = Board::unfilled();
+        board.cells[40] = Cell::Filled(5.try_into().unwrap());
+        board.cells[41] = Cell::Filled(6.try_into().unwrap());
+        assert!(is_valid_classic_at(&board, 41));
+        assert!(!rule.is_valid_at(&board, 41));
+        assert!(!rule.is_valid(&board));
+    }
+
+    #[test]
+    fn test_classic_rectangular_blocks() {
+        // 2 wide by 3 tall blocks, which the old block numbering merged across the middle.
+        let valid: Board<36, 6, 2, 3> = "142536253614361425415263526341634152".parse().unwrap();
+        assert!(ClassicSudoku {}.is_valid(&valid));
+        // Swapping the third and fourth rows keeps rows and columns intact but breaks the blocks.
+        let invalid: Board<36, 6, 2, 3> = "142536253614415263361425526341634152".parse().unwrap();
+        assert!(!ClassicSudoku {}.is_valid(&invalid));
+    }
+
+    #[test]
+    fn test_classic_grid_sizes() {
+        fn check<
+            const NUM_CELLS: usize,
+            const LENGTH: usize,
+            const BOX_WIDTH: usize,
+            const BOX_HEIGHT: usize,
+        >() {
+            let rules = ClassicSudoku {};
+            let solution: Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> =
+                crate::create::create_puzzle_solution(&rules).unwrap();
+            assert!(rules.is_valid(&solution));
+            // Shifting the rows up keeps rows and columns intact. The exact cover solver numbers
+            // blocks separately and must agree on whether the blocks still hold.
+            let mut shifted = solution.clone();
+            shifted.cells.rotate_left(LENGTH);
+            assert_eq!(
+                rules.is_valid(&shifted),
+                matches!(
+                    crate::dlx::solve_classic(&shifted),
+                    SolveResult::UniqueSolution(_)
+                )
+            );
+        }
+        check::<16, 4, 2, 2>();
+        check::<36, 6, 3, 2>();
+        check::<36, 6, 2, 3>();
+        check::<64, 8, 4, 2>();
+        check::<100, 10, 5, 2>();
+        check::<144, 12, 4, 3>();
+        check::<144, 12, 3, 4>();
+    }
 }
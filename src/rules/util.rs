@@ -22,7 +22,8 @@ pub fn is_valid_classic<
         let value_index: usize = (v.get() - 1).into();
         let row = i / LENGTH;
         let column = i % LENGTH;
-        let block = (row / BOX_HEIGHT) * BOX_WIDTH + column / BOX_WIDTH;
+        // Blocks are numbered left to right, with LENGTH / BOX_WIDTH of them in each band.
+        let block = (row / BOX_HEIGHT) * (LENGTH / BOX_WIDTH) + column / BOX_WIDTH;
 
         if row_values[row][value_index] {
             return false;
@@ -158,3 +159,289 @@ fn consecutive_val(c: Cell, v: u8) -> bool {
     c == Cell::Filled(NonZeroU8::new(v + 1).unwrap())
         || v >= 2 && c == Cell::Filled(NonZeroU8::new(v - 1).unwrap())
 }
+
+// The rows, then columns, then blocks of a classic grid.
+pub fn classic_units<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>() -> Vec<Vec<usize>> {
+    let rows = (0..LENGTH).map(|row| (0..LENGTH).map(|i| row * LENGTH + i).collect());
+    let columns = (0..LENGTH).map(|column| (0..LENGTH).map(|i| i * LENGTH + column).collect());
+    let blocks = (0..LENGTH).map(|block| {
+        let top = block / (LENGTH / BOX_WIDTH) * BOX_HEIGHT;
+        let left = block % (LENGTH / BOX_WIDTH) * BOX_WIDTH;
+        (0..LENGTH)
+            .map(|i| (top + i / BOX_WIDTH) * LENGTH + left + i % BOX_WIDTH)
+            .collect()
+    });
+    rows.chain(columns).chain(blocks).collect()
+}
+
+pub fn propagate_classic<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    // The placed digit can no longer appear elsewhere in its row, column, or block.
+    let v = match board.cells[index] {
+        Cell::Unfilled => return true,
+        Cell::Filled(v) => v,
+    };
+    let row = index / LENGTH;
+    let column = index % LENGTH;
+    let top = row / BOX_HEIGHT * BOX_HEIGHT;
+    let left = column / BOX_WIDTH * BOX_WIDTH;
+    for i in 0..LENGTH {
+        if !eliminate(board, row * LENGTH + i, v)
+            || !eliminate(board, i * LENGTH + column, v)
+            || !eliminate(
+                board,
+                (top + i / BOX_WIDTH) * LENGTH + left + i % BOX_WIDTH,
+                v,
+            )
+        {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn propagate_irregular<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    // The placed digit can no longer appear elsewhere in its row or column.
+    let v = match board.cells[index] {
+        Cell::Unfilled => return true,
+        Cell::Filled(v) => v,
+    };
+    let row = index / LENGTH;
+    let column = index % LENGTH;
+    (0..LENGTH)
+        .all(|i| eliminate(board, row * LENGTH + i, v) && eliminate(board, i * LENGTH + column, v))
+}
+
+pub fn propagate_knights_move<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    let v = match board.cells[index] {
+        Cell::Unfilled => return true,
+        Cell::Filled(v) => v,
+    };
+    KNIGHTS_MOVES
+        .iter()
+        .all(|&(dr, dc)| match offset::<LENGTH>(index, dr, dc) {
+            None => true,
+            Some(i) => eliminate(board, i, v),
+        })
+}
+
+pub fn propagate_kings_move<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    let v = match board.cells[index] {
+        Cell::Unfilled => return true,
+        Cell::Filled(v) => v,
+    };
+    DIAGONAL_MOVES
+        .iter()
+        .all(|&(dr, dc)| match offset::<LENGTH>(index, dr, dc) {
+            None => true,
+            Some(i) => eliminate(board, i, v),
+        })
+}
+
+pub fn propagate_nonconsecutive<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    let v = match board.cells[index] {
+        Cell::Unfilled => return true,
+        Cell::Filled(v) => v.get(),
+    };
+    let lower = NonZeroU8::new(v - 1);
+    let higher = NonZeroU8::new(v + 1);
+    ORTHOGONAL_MOVES
+        .iter()
+        .all(|&(dr, dc)| match offset::<LENGTH>(index, dr, dc) {
+            None => true,
+            Some(i) => {
+                lower.map_or(true, |d| eliminate(board, i, d))
+                    && higher.map_or(true, |d| eliminate(board, i, d))
+            }
+        })
+}
+
+pub(crate) const KNIGHTS_MOVES: [(isize, isize); 8] = [
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (-2, -1),
+    (-2, 1),
+    (2, -1),
+    (2, 1),
+];
+
+pub(crate) const DIAGONAL_MOVES: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+pub(crate) const ORTHOGONAL_MOVES: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+// The index of the cell the given number of rows and columns away, if it is on the board.
+pub(crate) fn offset<const LENGTH: usize>(index: usize, dr: isize, dc: isize) -> Option<usize> {
+    let row = (index / LENGTH) as isize + dr;
+    let column = (index % LENGTH) as isize + dc;
+    let length = LENGTH as isize;
+    if row < 0 || row >= length || column < 0 || column >= length {
+        return None;
+    }
+    Some((row * length + column) as usize)
+}
+
+// Removes a candidate from an unfilled cell. Returns false if the cell has no candidates left.
+pub(crate) fn eliminate<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+    digit: NonZeroU8,
+) -> bool {
+    if board.cells[index] != Cell::Unfilled {
+        return true;
+    }
+    board.remove_candidate(index, digit);
+    !board.candidates(index).is_empty()
+}
+
+pub fn is_valid_classic_at<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    // The changed cell must not share a digit with any other cell in its row, column, or block.
+    let cell = board.cells[index];
+    if cell == Cell::Unfilled {
+        return true;
+    }
+    let row = index / LENGTH;
+    let column = index % LENGTH;
+    let top = row / BOX_HEIGHT * BOX_HEIGHT;
+    let left = column / BOX_WIDTH * BOX_WIDTH;
+    (0..LENGTH).all(|i| {
+        let in_row = row * LENGTH + i;
+        let in_column = i * LENGTH + column;
+        let in_block = (top + i / BOX_WIDTH) * LENGTH + left + i % BOX_WIDTH;
+        (in_row == index || board.cells[in_row] != cell)
+            && (in_column == index || board.cells[in_column] != cell)
+            && (in_block == index || board.cells[in_block] != cell)
+    })
+}
+
+pub fn is_valid_irregular_at<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    // The changed cell must not share a digit with any other cell in its row or column.
+    let cell = board.cells[index];
+    if cell == Cell::Unfilled {
+        return true;
+    }
+    let row = index / LENGTH;
+    let column = index % LENGTH;
+    (0..LENGTH).all(|i| {
+        let in_row = row * LENGTH + i;
+        let in_column = i * LENGTH + column;
+        (in_row == index || board.cells[in_row] != cell)
+            && (in_column == index || board.cells[in_column] != cell)
+    })
+}
+
+pub fn passes_knights_move_constraint_at<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    let cell = board.cells[index];
+    cell == Cell::Unfilled
+        || KNIGHTS_MOVES.iter().all(|&(dr, dc)| {
+            offset::<LENGTH>(index, dr, dc).map_or(true, |i| board.cells[i] != cell)
+        })
+}
+
+pub fn passes_kings_move_constraint_at<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    let cell = board.cells[index];
+    cell == Cell::Unfilled
+        || DIAGONAL_MOVES.iter().all(|&(dr, dc)| {
+            offset::<LENGTH>(index, dr, dc).map_or(true, |i| board.cells[i] != cell)
+        })
+}
+
+pub fn passes_nonconsecutive_constraint_at<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+) -> bool {
+    let v = match board.cells[index] {
+        Cell::Unfilled => return true,
+        Cell::Filled(v) => v.get(),
+    };
+    ORTHOGONAL_MOVES.iter().all(|&(dr, dc)| {
+        offset::<LENGTH>(index, dr, dc).map_or(true, |i| !consecutive_val(board.cells[i], v))
+    })
+}
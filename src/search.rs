@@ -0,0 +1,442 @@
+use crate::candidates::Candidates;
+use crate::rules::PuzzleRules;
+use crate::Board;
+use crate::Cell;
+use rand::{Rng, RngCore};
+use std::convert::TryInto;
+use std::error::Error;
+use std::fmt;
+use std::num::NonZeroU8;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Limits on how long a search may run. A search stops at the first limit it reaches.
+#[derive(Clone, Debug, Default)]
+pub struct SearchBudget {
+    max_nodes: Option<u64>,
+    deadline: Option<Instant>,
+    cancel: Option<Arc<AtomicBool>>,
+}
+
+impl SearchBudget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    // The maximum number of digits the search may place.
+    pub fn max_nodes(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    // The search stops once the flag is set, which may happen from another thread.
+    pub fn cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    // The budget left after a search which used the given statistics.
+    pub(crate) fn remaining(&self, stats: &SearchStats) -> Self {
+        let mut budget = self.clone();
+        budget.max_nodes = self.max_nodes.map(|n| n.saturating_sub(stats.nodes));
+        budget
+    }
+
+    // This budget with no more than the given number of nodes.
+    pub(crate) fn limit_nodes(&self, max_nodes: u64) -> Self {
+        let mut budget = self.clone();
+        budget.max_nodes = Some(self.max_nodes.map_or(max_nodes, |n| n.min(max_nodes)));
+        budget
+    }
+
+    pub(crate) fn is_exhausted(&self, stats: &SearchStats) -> bool {
+        self.max_nodes.map_or(false, |n| stats.nodes >= n)
+            || self.deadline.map_or(false, |d| Instant::now() >= d)
+            || self
+                .cancel
+                .as_ref()
+                .map_or(false, |c| c.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    // Digits placed by the search.
+    pub nodes: u64,
+    // Placements rejected by the rules plus cells whose digits were all tried.
+    pub backtracks: u64,
+    // Most cells guessed at once.
+    pub max_depth: usize,
+    // Calls to is_valid and is_valid_at.
+    pub is_valid_calls: u64,
+    // Time spent in the rules, split between checking and propagating. Only measured by the
+    // functions which return statistics and zero elsewhere. A set of rules is timed as a whole,
+    // not per constraint.
+    pub is_valid_time: Duration,
+    pub propagate_calls: u64,
+    pub propagate_time: Duration,
+    pub elapsed: Duration,
+}
+
+impl SearchStats {
+    // Adds the work of another search to these statistics.
+    pub fn accumulate(&mut self, other: &SearchStats) {
+        self.nodes += other.nodes;
+        self.backtracks += other.backtracks;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.is_valid_calls += other.is_valid_calls;
+        self.is_valid_time += other.is_valid_time;
+        self.propagate_calls += other.propagate_calls;
+        self.propagate_time += other.propagate_time;
+        self.elapsed += other.elapsed;
+    }
+}
+
+// Returned when a search runs out of budget, along with the work done so far.
+#[derive(Debug)]
+pub struct Aborted {
+    pub stats: SearchStats,
+}
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Search aborted after {} nodes", self.stats.nodes)
+    }
+}
+
+impl Error for Aborted {}
+
+// Depth first search shared by the solvers and the puzzle creator.
+// Branches on the unfilled cell with the fewest candidates left, trying its digits in ascending order
+// or in a random order if given a random number generator. A digit with a single cell left in one
+// of the units of the rules is placed before guessing.
+pub(crate) struct Search<
+    'a,
+    R,
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+> {
+    rules: &'a R,
+    rng: Option<&'a mut dyn RngCore>,
+    // Units of the rules, read once as they are checked at every guess.
+    units: Vec<Vec<usize>>,
+    budget: SearchBudget,
+    stats: SearchStats,
+    // Whether calls into the rules are timed, which costs a clock read per call.
+    timed: bool,
+    started: Instant,
+    aborted: bool,
+    stack: Vec<Frame<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+    solution: Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+}
+
+struct Frame<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+> {
+    board: Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    index: usize,
+    // Digits left to try, with the next one at the end.
+    guesses: Vec<NonZeroU8>,
+}
+
+impl<
+        'a,
+        R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > Search<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    pub(crate) fn new(
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        rules: &'a R,
+    ) -> Self {
+        Self::start(board, rules, None, SearchBudget::unlimited(), false)
+    }
+
+    pub(crate) fn with_budget(
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        rules: &'a R,
+        budget: SearchBudget,
+    ) -> Self {
+        Self::start(board, rules, None, budget, false)
+    }
+
+    // Like with_budget, but also measures the time spent in the rules.
+    pub(crate) fn timed(
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        rules: &'a R,
+        budget: SearchBudget,
+    ) -> Self {
+        Self::start(board, rules, None, budget, true)
+    }
+
+    pub(crate) fn shuffled(
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        rules: &'a R,
+        rng: &'a mut dyn RngCore,
+        budget: SearchBudget,
+    ) -> Self {
+        Self::start(board, rules, Some(rng), budget, false)
+    }
+
+    fn start(
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        rules: &'a R,
+        rng: Option<&'a mut dyn RngCore>,
+        budget: SearchBudget,
+        timed: bool,
+    ) -> Self {
+        let started = Instant::now();
+        let mut search = Search {
+            rules,
+            rng,
+            units: rules.units(),
+            budget,
+            stats: SearchStats::default(),
+            timed,
+            started,
+            aborted: false,
+            stack: Vec::new(),
+            solution: None,
+        };
+        // Candidates are rebuilt from the digits so cells cleared by callers are searched in full.
+        let mut board = board.clone();
+        board.reset_candidates();
+        if search.is_valid(&board) && search.propagate_filled(&mut board) {
+            search.push(board);
+        }
+        search
+    }
+
+    pub(crate) fn next_solution(
+        &mut self,
+    ) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
+        loop {
+            if let Some(solution) = self.solution.take() {
+                return Some(solution);
+            }
+            if self.stack.is_empty() {
+                return None;
+            }
+            if self.budget.is_exhausted(&self.stats) {
+                // Give up on the rest of the search.
+                self.aborted = true;
+                self.stack.clear();
+                return None;
+            }
+            let frame = self.stack.last_mut().unwrap();
+            let guess = match frame.guesses.pop() {
+                None => {
+                    self.stack.pop();
+                    if !self.stack.is_empty() {
+                        self.stats.backtracks += 1;
+                    }
+                    continue;
+                }
+                Some(v) => v,
+            };
+            let index = frame.index;
+            let mut board = frame.board.clone();
+            board.cells[index] = Cell::Filled(guess);
+            self.stats.nodes += 1;
+            self.stats.max_depth = self.stats.max_depth.max(self.stack.len());
+            if self.is_valid_at(&board, index) && self.propagate(&mut board, index) {
+                self.push(board);
+            } else {
+                self.stats.backtracks += 1;
+            }
+        }
+    }
+
+    fn is_valid(&mut self, board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> bool {
+        let started = self.clock();
+        let valid = self.rules.is_valid(board);
+        self.stats.is_valid_calls += 1;
+        if let Some(started) = started {
+            self.stats.is_valid_time += started.elapsed();
+        }
+        valid
+    }
+
+    fn is_valid_at(
+        &mut self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        let started = self.clock();
+        let valid = self.rules.is_valid_at(board, index);
+        self.stats.is_valid_calls += 1;
+        if let Some(started) = started {
+            self.stats.is_valid_time += started.elapsed();
+        }
+        valid
+    }
+
+    fn propagate(
+        &mut self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        index: usize,
+    ) -> bool {
+        let started = self.clock();
+        let consistent = self.rules.propagate(board, index);
+        self.stats.propagate_calls += 1;
+        if let Some(started) = started {
+            self.stats.propagate_time += started.elapsed();
+        }
+        consistent
+    }
+
+    fn clock(&self) -> Option<Instant> {
+        if self.timed {
+            Some(Instant::now())
+        } else {
+            None
+        }
+    }
+
+    // Propagates every digit already on the board. Returns false if a contradiction is found.
+    fn propagate_filled(
+        &mut self,
+        board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    ) -> bool {
+        (0..NUM_CELLS).all(|i| board.cells[i] == Cell::Unfilled || self.propagate(board, i))
+    }
+
+    // One board per digit of the first cell the search branches on, which together cover the
+    // remaining search. Returns None if the search has no branching cell left.
+    pub(crate) fn branches(&self) -> Option<Vec<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>> {
+        if self.solution.is_some() || self.stack.len() != 1 {
+            return None;
+        }
+        let frame = &self.stack[0];
+        let branches = frame
+            .guesses
+            .iter()
+            .rev()
+            .map(|&guess| {
+                let mut board = frame.board.clone();
+                board.cells[frame.index] = Cell::Filled(guess);
+                board
+            })
+            .collect();
+        Some(branches)
+    }
+
+    // Whether the search stopped early because it ran out of budget.
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    pub(crate) fn stats(&self) -> SearchStats {
+        SearchStats {
+            elapsed: self.started.elapsed(),
+            ..self.stats.clone()
+        }
+    }
+
+    fn push(&mut self, board: Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) {
+        // Find the empty cell with the fewest candidates.
+        let index = board
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_i, &cell)| matches!(cell, Cell::Unfilled))
+            .min_by_key(|&(i, _cell)| board.candidates[i].len())
+            .map(|e| e.0);
+        let index = match index {
+            None => {
+                self.solution = Some(board);
+                return;
+            }
+            Some(v) => v,
+        };
+        let (index, guesses) = match board.candidates[index].len() {
+            1 => (index, board.candidates[index].iter().collect()),
+            _ => match self.hidden_single(&board) {
+                Some(Some((i, digit))) => (i, vec![digit]),
+                // Some digit has nowhere left to go.
+                Some(None) => return,
+                None => {
+                    let mut guesses: Vec<_> = board.candidates[index].iter().collect();
+                    guesses.reverse();
+                    if let Some(rng) = self.rng.as_mut() {
+                        shuffle(&mut guesses, *rng);
+                    }
+                    (index, guesses)
+                }
+            },
+        };
+        self.stack.push(Frame {
+            board,
+            index,
+            guesses,
+        });
+    }
+
+    // Finds a digit which has a single cell left in one of the units. Returns Some(None) if a
+    // digit has no cell left in some unit.
+    fn hidden_single(
+        &self,
+        board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    ) -> Option<Option<(usize, NonZeroU8)>> {
+        for unit in self.units.iter() {
+            let mut placed = Candidates::empty();
+            let mut once = Candidates::empty();
+            let mut twice = Candidates::empty();
+            for &i in unit.iter() {
+                match board.cells[i] {
+                    Cell::Filled(v) => placed.insert(v),
+                    Cell::Unfilled => {
+                        twice = twice.union(once.intersection(board.candidates[i]));
+                        once = once.union(board.candidates[i]);
+                    }
+                }
+            }
+            if !Candidates::all(LENGTH)
+                .difference(placed)
+                .difference(once)
+                .is_empty()
+            {
+                return Some(None);
+            }
+            if let Some(digit) = once.difference(twice).difference(placed).iter().next() {
+                let index = unit
+                    .iter()
+                    .copied()
+                    .find(|&i| {
+                        board.cells[i] == Cell::Unfilled && board.candidates[i].contains(digit)
+                    })
+                    .unwrap();
+                return Some(Some((index, digit)));
+            }
+        }
+        None
+    }
+}
+
+// Fisher-Yates shuffle which only samples u32 values, unlike SliceRandom::shuffle whose draws
+// depend on the width of usize, so a seeded generator gives the same order on every platform.
+pub(crate) fn shuffle<T, G: RngCore + ?Sized>(items: &mut [T], rng: &mut G) {
+    for i in (1..items.len()).rev() {
+        let bound: u32 = (i + 1).try_into().unwrap();
+        let j: u32 = rng.gen_range(0, bound);
+        items.swap(i, j as usize);
+    }
+}
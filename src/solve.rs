@@ -1,7 +1,7 @@
 use crate::rules::PuzzleRules;
+use crate::search::{Aborted, Search, SearchBudget, SearchStats};
 use crate::Board;
 use crate::Cell;
-use std::convert::TryInto;
 
 #[derive(Debug, PartialEq)]
 pub enum SolveResult<
@@ -24,44 +24,127 @@ pub fn solve<
     board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
     rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
 ) -> SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
-    if !rules.is_valid(board) {
-        return SolveResult::NoSolution;
+    first_two(solutions(board, rules))
+}
+
+pub fn solve_with_budget<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    budget: &SearchBudget,
+) -> Result<SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>, Aborted> {
+    run(board, rules, budget, false, |solutions| {
+        first_two(solutions)
+    })
+    .map(|(result, _stats)| result)
+}
+
+pub fn solve_with_stats<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    budget: &SearchBudget,
+) -> Result<
+    (
+        SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        SearchStats,
+    ),
+    Aborted,
+> {
+    run(board, rules, budget, true, |solutions| first_two(solutions))
+}
+
+pub(crate) fn first_two<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    mut solutions: impl Iterator<Item = Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+) -> SolveResult<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    match (solutions.next(), solutions.next()) {
+        (None, _) => SolveResult::NoSolution,
+        (Some(b), None) => SolveResult::UniqueSolution(b),
+        (Some(_), Some(b)) => SolveResult::MultipleSolutions(b),
     }
-    // Find an empty cell.
-    let index = board
-        .cells
-        .iter()
-        .enumerate()
-        .find(|(_i, &cell)| matches!(cell, Cell::Unfilled))
-        .map(|e| e.0);
-    let index = match index {
-        None => return SolveResult::UniqueSolution(board.clone()),
-        Some(v) => v,
-    };
-    let mut current_result = SolveResult::NoSolution;
-    for guess in 1..=LENGTH {
-        let guess: u8 = guess.try_into().unwrap();
-        board.cells[index] = Cell::Filled(guess.try_into().unwrap());
-        let sub_result = solve(board, rules);
-        match (sub_result, &current_result) {
-            (SolveResult::NoSolution, _) => (),
-            (SolveResult::UniqueSolution(b), SolveResult::NoSolution) => {
-                current_result = SolveResult::UniqueSolution(b);
-            }
-            (SolveResult::UniqueSolution(b), SolveResult::UniqueSolution(_)) => {
-                current_result = SolveResult::MultipleSolutions(b);
-                break;
-            }
-            (SolveResult::UniqueSolution(_), SolveResult::MultipleSolutions(_)) => (),
-            (SolveResult::MultipleSolutions(b), _) => {
-                current_result = SolveResult::MultipleSolutions(b);
-                break;
-            }
-        }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolutionCount {
+    Exact(usize),
+    // The search stopped after finding one more solution than the limit.
+    MoreThan(usize),
+}
+
+pub fn count_solutions<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    limit: usize,
+) -> SolutionCount {
+    count_up_to(solutions(board, rules), limit)
+}
+
+pub fn count_solutions_with_budget<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    limit: usize,
+    budget: &SearchBudget,
+) -> Result<SolutionCount, Aborted> {
+    run(board, rules, budget, false, |solutions| {
+        count_up_to(solutions, limit)
+    })
+    .map(|(result, _stats)| result)
+}
+
+pub fn count_solutions_with_stats<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    limit: usize,
+    budget: &SearchBudget,
+) -> Result<(SolutionCount, SearchStats), Aborted> {
+    run(board, rules, budget, true, |solutions| {
+        count_up_to(solutions, limit)
+    })
+}
+
+fn count_up_to<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    solutions: impl Iterator<Item = Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+    limit: usize,
+) -> SolutionCount {
+    let count = solutions.take(limit.saturating_add(1)).count();
+    if count > limit {
+        SolutionCount::MoreThan(limit)
+    } else {
+        SolutionCount::Exact(count)
     }
-    // Make sure we exit this function with the board unchanged.
-    board.cells[index] = Cell::Unfilled;
-    current_result
 }
 
 pub fn solve_one<
@@ -73,33 +156,39 @@ pub fn solve_one<
     board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
     rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
 ) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
-    if !rules.is_valid(board) {
-        return None;
-    }
-    // Find an empty cell.
-    let index = board
-        .cells
-        .iter()
-        .enumerate()
-        .find(|(_i, &cell)| matches!(cell, Cell::Unfilled))
-        .map(|e| e.0);
-    let index = match index {
-        None => return Some(board.clone()),
-        Some(v) => v,
-    };
-    for guess in 1..=LENGTH {
-        let guess: u8 = guess.try_into().unwrap();
-        board.cells[index] = Cell::Filled(guess.try_into().unwrap());
-        match solve_one(board, rules) {
-            None => (),
-            Some(b) => {
-                return Some(b);
-            }
-        }
-    }
-    // Make sure we exit this function with the board unchanged if we did not find a solution.
-    board.cells[index] = Cell::Unfilled;
-    None
+    solutions(board, rules).next()
+}
+
+pub fn solve_one_with_budget<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    budget: &SearchBudget,
+) -> Result<Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>, Aborted> {
+    run(board, rules, budget, false, |solutions| solutions.next()).map(|(result, _stats)| result)
+}
+
+pub fn solve_one_with_stats<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    budget: &SearchBudget,
+) -> Result<
+    (
+        Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+        SearchStats,
+    ),
+    Aborted,
+> {
+    run(board, rules, budget, true, |solutions| solutions.next())
 }
 
 pub fn derive<
@@ -111,55 +200,179 @@ pub fn derive<
     board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
     rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
 ) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
-    let mut acc = None;
-    derive_recursive(board, rules, &mut acc);
-    acc
+    intersect(solutions(board, rules))
 }
 
-fn derive_recursive<
+pub fn derive_with_budget<
     const NUM_CELLS: usize,
     const LENGTH: usize,
     const BOX_WIDTH: usize,
     const BOX_HEIGHT: usize,
 >(
-    board: &mut Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
     rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
-    acc: &mut Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
-) {
-    if !rules.is_valid(board) {
-        return;
-    }
-    // Find an empty cell.
-    let index = board
-        .cells
-        .iter()
-        .enumerate()
-        .find(|(_i, &cell)| matches!(cell, Cell::Unfilled))
-        .map(|e| e.0);
-    let index = match index {
-        None => {
-            match acc {
-                None => *acc = Some(board.clone()),
-                Some(acc) => {
-                    for (dst, src) in acc.cells.iter_mut().zip(board.cells.iter()) {
-                        if *dst != *src {
-                            *dst = Cell::Unfilled;
-                        }
-                    }
-                }
+    budget: &SearchBudget,
+) -> Result<Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>, Aborted> {
+    run(board, rules, budget, false, |solutions| {
+        intersect(solutions)
+    })
+    .map(|(result, _stats)| result)
+}
+
+pub fn derive_with_stats<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &impl PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    budget: &SearchBudget,
+) -> Result<
+    (
+        Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+        SearchStats,
+    ),
+    Aborted,
+> {
+    run(board, rules, budget, true, |solutions| intersect(solutions))
+}
+
+fn intersect<
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    mut solutions: impl Iterator<Item = Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>>,
+) -> Option<Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>> {
+    // Keep only the digits which every solution agrees on.
+    let mut acc = solutions.next()?;
+    for solution in solutions {
+        for (dst, src) in acc.cells.iter_mut().zip(solution.cells.iter()) {
+            if *dst != *src {
+                *dst = Cell::Unfilled;
             }
-            dbg!(acc);
-            return;
         }
-        Some(v) => v,
-    };
-    for guess in 1..=LENGTH {
-        let guess: u8 = guess.try_into().unwrap();
-        board.cells[index] = Cell::Filled(guess.try_into().unwrap());
-        derive_recursive(board, rules, acc);
     }
-    // Make sure we exit this function with the board unchanged.
-    board.cells[index] = Cell::Unfilled;
+    acc.reset_candidates();
+    Some(acc)
+}
+
+// Yields the solutions of a board one at a time, in the order the search finds them.
+pub struct Solutions<
+    'a,
+    R,
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+> {
+    search: Search<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+}
+
+impl<
+        'a,
+        R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > Solutions<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    // Whether the iterator ended because the budget ran out rather than because every solution was found.
+    pub fn is_aborted(&self) -> bool {
+        self.search.is_aborted()
+    }
+
+    pub fn stats(&self) -> SearchStats {
+        self.search.stats()
+    }
+
+    fn finish<T>(&self, result: T) -> Result<(T, SearchStats), Aborted> {
+        if self.is_aborted() {
+            return Err(Aborted {
+                stats: self.stats(),
+            });
+        }
+        Ok((result, self.stats()))
+    }
+}
+
+impl<
+        'a,
+        R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > Iterator for Solutions<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    type Item = Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.search.next_solution()
+    }
+}
+
+pub fn solutions<
+    'a,
+    R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &'a R,
+) -> Solutions<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    Solutions {
+        search: Search::new(board, rules),
+    }
+}
+
+pub fn solutions_with_budget<
+    'a,
+    R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &'a R,
+    budget: &SearchBudget,
+) -> Solutions<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+    Solutions {
+        search: Search::with_budget(board, rules, budget.clone()),
+    }
+}
+
+// Runs a search within the budget and reports its statistics, timing the rules only if asked to.
+fn run<
+    'a,
+    T,
+    R: PuzzleRules<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    const NUM_CELLS: usize,
+    const LENGTH: usize,
+    const BOX_WIDTH: usize,
+    const BOX_HEIGHT: usize,
+>(
+    board: &Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>,
+    rules: &'a R,
+    budget: &SearchBudget,
+    timed: bool,
+    consume: impl FnOnce(&mut Solutions<'a, R, NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>) -> T,
+) -> Result<(T, SearchStats), Aborted> {
+    let mut solutions = Solutions {
+        search: if timed {
+            Search::timed(board, rules, budget.clone())
+        } else {
+            Search::with_budget(board, rules, budget.clone())
+        },
+    };
+    let result = consume(&mut solutions);
+    solutions.finish(result)
 }
 
 #[cfg(test)]
@@ -167,6 +380,11 @@ mod tests {
     use super::*;
     use crate::rules::ClassicSudoku;
     use crate::rules::KnightsRestrictionSudoku;
+    use crate::rules::Miracle;
+    use std::convert::TryInto;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
 
     #[test]
     fn test_unique_solution() {
@@ -185,6 +403,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_unique_solution_16x16() {
+        // Four rows of the grid on each line.
+        let mut puzzle: Board<256, 16, 4, 4> =
+            "05C0D007000E60000E02000A6704008900000G900208100C408036000B500070\
+             C1200B000000E007000G0000090D830004DE03C9G5000200030000GE410000D6\
+             FG0000D6E8000090004000B52CF0A610001BF0400000C000600500000040073F\
+             02000DE000B6010A9001206004A00000D80010AG70004060000C700010090F20"
+                .parse()
+                .unwrap();
+        let solved: Board<256, 16, 4, 4> =
+            "A5CFD8179G3E6B421EG2B5FA67C43D893B67EG94A2D815FC498D362CFB51GA7E\
+             C1264B5D3A8FE9G7BAFG6172C9ED835474DE83C9G56BF2A18359AFGE4172BCD6\
+             FG3AC2D6E817549BED4897B52CF3A61G271BFA43569GC8ED6C95GE81BD4A273F\
+             G2745DEF83B691CA9FE12C68D4A57GB3D8B319AG7F2C4E6556AC743B1EG9DF28"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            solve(&mut puzzle, &ClassicSudoku {}),
+            SolveResult::UniqueSolution(solved)
+        );
+    }
+
     #[test]
     fn test_multiple_solutions() {
         let mut puzzle: Board<81, 9, 3, 3> = Board::unfilled();
@@ -196,9 +437,8 @@ mod tests {
 
     #[test]
     fn test_no_solutions() {
-        let mut puzzle: Board<81, 9, 3, 3> = Board {
-            cells: [Cell::Filled((1).try_into().unwrap()); 81],
-        };
+        let mut puzzle: Board<81, 9, 3, 3> =
+            Board::from_cells([Cell::Filled((1).try_into().unwrap()); 81]);
         assert!(matches!(
             solve(&mut puzzle, &ClassicSudoku {}),
             SolveResult::NoSolution
@@ -280,4 +520,124 @@ mod tests {
             SolveResult::UniqueSolution(solution)
         );
     }
+
+    #[test]
+    fn test_hardest_solution() {
+        // AI Escargot.
+        let mut puzzle: Board<81, 9, 3, 3> =
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300"
+                .parse()
+                .unwrap();
+        let solved: Board<81, 9, 3, 3> =
+            "162857493534129678789643521475312986913586742628794135356478219241935867897261354"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            solve(&mut puzzle, &ClassicSudoku {}),
+            SolveResult::UniqueSolution(solved)
+        );
+    }
+
+    #[test]
+    fn test_miracle_solve_one() {
+        let mut puzzle: Board<81, 9, 3, 3> = Board::unfilled();
+        let solution = solve_one(&mut puzzle, &Miracle {}).unwrap();
+        assert!(Miracle {}.is_valid(&solution));
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let puzzle: Board<16, 4, 2, 2> = Board::unfilled();
+        assert_eq!(
+            count_solutions(&puzzle, &ClassicSudoku {}, 1000),
+            SolutionCount::Exact(288)
+        );
+        assert_eq!(
+            count_solutions(&puzzle, &ClassicSudoku {}, 288),
+            SolutionCount::Exact(288)
+        );
+        assert_eq!(
+            count_solutions(&puzzle, &ClassicSudoku {}, 10),
+            SolutionCount::MoreThan(10)
+        );
+        let puzzle: Board<81, 9, 3, 3> =
+            "000075400000000008080190000300001060000000034000068170204000603900000020530200000"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            count_solutions(&puzzle, &ClassicSudoku {}, 10),
+            SolutionCount::Exact(1)
+        );
+    }
+
+    #[test]
+    fn test_solutions_iterator() {
+        let puzzle: Board<16, 4, 2, 2> = "1234000000000000".parse().unwrap();
+        let rules = ClassicSudoku {};
+        let all: Vec<_> = solutions(&puzzle, &rules).collect();
+        assert_eq!(all.len(), 12);
+        assert!(all.iter().all(|b| rules.is_valid(b)));
+        assert!(all.iter().all(|b| b.cells[..4] == puzzle.cells[..4]));
+        assert!((1..all.len()).all(|i| all[..i].iter().all(|b| *b != all[i])));
+        assert_eq!(solutions(&puzzle, &rules).nth(3), Some(all[3].clone()));
+    }
+
+    #[test]
+    fn test_budget() {
+        let puzzle: Board<81, 9, 3, 3> = Board::unfilled();
+        let budget = SearchBudget::unlimited().max_nodes(100);
+        let aborted = derive_with_budget(&puzzle, &ClassicSudoku {}, &budget).unwrap_err();
+        assert_eq!(aborted.stats.nodes, 100);
+        assert!(matches!(
+            solve_with_budget(&puzzle, &ClassicSudoku {}, &budget),
+            Ok(SolveResult::MultipleSolutions(_))
+        ));
+
+        let cancel = Arc::new(AtomicBool::new(true));
+        let budget = SearchBudget::unlimited().cancel_flag(cancel.clone());
+        assert!(solve_one_with_budget(&puzzle, &Miracle {}, &budget).is_err());
+        cancel.store(false, Ordering::Relaxed);
+        assert!(solve_one_with_budget(&puzzle, &Miracle {}, &budget).is_ok());
+
+        let budget = SearchBudget::unlimited().timeout(Duration::from_millis(10));
+        let aborted = count_solutions_with_budget(&puzzle, &ClassicSudoku {}, usize::MAX, &budget)
+            .unwrap_err();
+        assert!(aborted.stats.elapsed >= Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_stats() {
+        let puzzle: Board<81, 9, 3, 3> =
+            "000075400000000008080190000300001060000000034000068170204000603900000020530200000"
+                .parse()
+                .unwrap();
+        let (result, stats) =
+            solve_with_stats(&puzzle, &ClassicSudoku {}, &SearchBudget::unlimited()).unwrap();
+        assert!(matches!(result, SolveResult::UniqueSolution(_)));
+        assert!(stats.nodes > 0);
+        assert!(stats.backtracks > 0);
+        assert!(stats.max_depth > 0 && stats.max_depth <= 81);
+        // One full check up front and one incremental check per node.
+        assert_eq!(stats.is_valid_calls, stats.nodes + 1);
+        assert!(stats.propagate_calls >= stats.nodes);
+        assert!(stats.is_valid_time > Duration::from_secs(0));
+        assert!(stats.propagate_time > Duration::from_secs(0));
+
+        // The rules are only timed when statistics are asked for.
+        let mut untimed = solutions(&puzzle, &ClassicSudoku {});
+        assert!(untimed.next().is_some());
+        assert!(untimed.stats().nodes > 0);
+        assert_eq!(untimed.stats().is_valid_time, Duration::from_secs(0));
+        assert_eq!(untimed.stats().propagate_time, Duration::from_secs(0));
+
+        // A full board needs no search.
+        let solved: Board<81, 9, 3, 3> =
+            "693875412145632798782194356357421869816957234429368175274519683968743521531286947"
+                .parse()
+                .unwrap();
+        let (_, stats) =
+            solve_one_with_stats(&solved, &ClassicSudoku {}, &SearchBudget::unlimited()).unwrap();
+        assert_eq!(stats.nodes, 0);
+        assert_eq!(stats.max_depth, 0);
+    }
 }
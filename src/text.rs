@@ -0,0 +1,539 @@
+use crate::candidates::Candidates;
+use crate::Board;
+use crate::Cell;
+use crate::ParseError;
+use std::convert::TryInto;
+use std::num::NonZeroU8;
+
+// Ways of writing a board as text. Rows are read from left to right and then top to bottom.
+// Parsing skips whitespace, the grid drawing characters |, - and + and Unicode box drawing
+// characters, and accepts ., _ and * as blanks, so puzzles pasted in most layouts can be read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Notation {
+    // One character per cell: 1-9 followed by A-Z for 10 upwards, and 0 also for blanks.
+    Letters,
+    // One character per cell counting from zero: 0-9 followed by A-Z, so F is 16.
+    ZeroBased,
+    // Decimal numbers separated by commas or drawing characters, and 0 also for blanks.
+    Numbers,
+}
+
+// Settings for drawing a board with Board::render, built up like CreateOptions.
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    unicode: bool,
+    pencil_marks: bool,
+}
+
+impl RenderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Draws the lines with box drawing characters and a border around the grid.
+    pub fn unicode(mut self, unicode: bool) -> Self {
+        self.unicode = unicode;
+        self
+    }
+
+    // Shows the candidates of unfilled cells instead of a dot, and brackets the digits of filled
+    // cells so they stand apart from cells with a single candidate.
+    pub fn pencil_marks(mut self, pencil_marks: bool) -> Self {
+        self.pencil_marks = pencil_marks;
+        self
+    }
+}
+
+impl<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    > Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT>
+{
+    pub fn parse(s: &str, notation: Notation) -> Result<Self, ParseError> {
+        let mut values = Vec::new();
+        for (index, token) in tokens(s, notation) {
+            let cell = values.len();
+            let invalid = |offset: usize| ParseError::InvalidCharacter {
+                character: token[offset],
+                index: index + offset,
+                row: cell / LENGTH,
+                column: cell % LENGTH,
+            };
+            let value = match notation {
+                Notation::Letters => letter_value(token[0]).ok_or_else(|| invalid(0))?,
+                Notation::ZeroBased => zero_based_value(token[0]).ok_or_else(|| invalid(0))?,
+                Notation::Numbers => match token[..] {
+                    ['.'] | ['_'] | ['*'] => 0,
+                    _ => match token.iter().position(|c| !c.is_ascii_digit()) {
+                        Some(offset) => return Err(invalid(offset)),
+                        // Too many digits for a u32 are out of range anyway.
+                        None => token.iter().collect::<String>().parse().unwrap_or(u32::MAX),
+                    },
+                },
+            };
+            values.push((value, token.iter().collect::<String>()));
+        }
+        if values.len() != NUM_CELLS {
+            return Err(ParseError::WrongLength {
+                expected: NUM_CELLS,
+                found: values.len(),
+            });
+        }
+        let mut cells = [Cell::Unfilled; NUM_CELLS];
+        for (i, (value, text)) in values.into_iter().enumerate() {
+            if value as usize > LENGTH {
+                return Err(ParseError::DigitOutOfRange {
+                    digit: text,
+                    length: LENGTH,
+                    row: i / LENGTH,
+                    column: i % LENGTH,
+                });
+            }
+            let value: u8 = value.try_into().unwrap();
+            if let Some(v) = NonZeroU8::new(value) {
+                cells[i] = Cell::Filled(v);
+            }
+        }
+        Ok(Self::from_cells(cells))
+    }
+
+    // Draws the board one row per line with lines between the boxes. Digits are written as in
+    // Notation::Letters and blanks as dots, so the plain drawing parses back to the same board.
+    // Pencil marks leave out the digits already in a cell's row, column or block, along with any
+    // candidates removed from the board, such as by the steps of the logic module.
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let texts: Vec<String> = (0..NUM_CELLS)
+            .map(|i| match self.cells[i] {
+                Cell::Filled(v) if options.pencil_marks => format!("[{}]", letter(v.get())),
+                Cell::Filled(v) => letter(v.get()).to_string(),
+                Cell::Unfilled if options.pencil_marks => self
+                    .pencil_marks(i)
+                    .iter()
+                    .map(|d| letter(d.get()))
+                    .collect(),
+                Cell::Unfilled => ".".to_string(),
+            })
+            .collect();
+        let widths: Vec<usize> = (0..LENGTH)
+            .map(|column| {
+                (0..LENGTH)
+                    .map(|row| texts[row * LENGTH + column].len())
+                    .max()
+                    .unwrap()
+            })
+            .collect();
+        // Each stack of boxes is padded by a space on either side.
+        let stack_widths: Vec<usize> = widths
+            .chunks(BOX_WIDTH)
+            .map(|w| w.iter().sum::<usize>() + BOX_WIDTH + 1)
+            .collect();
+        let (vertical, horizontal) = if options.unicode {
+            ("│", '─')
+        } else {
+            ("|", '-')
+        };
+        // Without a border the outer padding is dropped.
+        let frame = |line: String, left: char, right: char| {
+            if options.unicode {
+                format!("{}{}{}", left, line, right)
+            } else {
+                line[1..line.len() - 1].trim_end().to_string()
+            }
+        };
+        let separator = |left: char, cross: char, right: char| {
+            let stacks: Vec<String> = stack_widths
+                .iter()
+                .map(|&w| horizontal.to_string().repeat(w))
+                .collect();
+            frame(stacks.join(&cross.to_string()), left, right)
+        };
+
+        let mut lines = Vec::new();
+        if options.unicode {
+            lines.push(separator('┌', '┬', '┐'));
+        }
+        for row in 0..LENGTH {
+            if row > 0 && row % BOX_HEIGHT == 0 {
+                lines.push(if options.unicode {
+                    separator('├', '┼', '┤')
+                } else {
+                    separator('-', '+', '-')
+                });
+            }
+            let stacks: Vec<String> = (0..LENGTH / BOX_WIDTH)
+                .map(|stack| {
+                    let cells: Vec<String> = (stack * BOX_WIDTH..(stack + 1) * BOX_WIDTH)
+                        .map(|column| {
+                            format!("{:<1$}", texts[row * LENGTH + column], widths[column])
+                        })
+                        .collect();
+                    format!(" {} ", cells.join(" "))
+                })
+                .collect();
+            lines.push(frame(stacks.join(vertical), '│', '│'));
+        }
+        if options.unicode {
+            lines.push(separator('└', '┴', '┘'));
+        }
+        lines.join("\n")
+    }
+
+    fn pencil_marks(&self, index: usize) -> Candidates {
+        let row = index / LENGTH;
+        let column = index % LENGTH;
+        let top = row / BOX_HEIGHT * BOX_HEIGHT;
+        let left = column / BOX_WIDTH * BOX_WIDTH;
+        let mut candidates = self.candidates[index];
+        for i in 0..LENGTH {
+            for &peer in [
+                row * LENGTH + i,
+                i * LENGTH + column,
+                (top + i / BOX_WIDTH) * LENGTH + left + i % BOX_WIDTH,
+            ]
+            .iter()
+            {
+                if let Cell::Filled(v) = self.cells[peer] {
+                    candidates.remove(v);
+                }
+            }
+        }
+        candidates
+    }
+
+    // Numbers are written one row per line.
+    pub fn format(&self, notation: Notation) -> String {
+        let mut s = String::new();
+        for (i, cell) in self.cells.iter().enumerate() {
+            let value = match cell {
+                Cell::Unfilled => 0,
+                Cell::Filled(v) => v.get(),
+            };
+            match notation {
+                Notation::Letters => s.push(letter(value)),
+                Notation::ZeroBased => s.push(match value {
+                    0 => '.',
+                    _ => std::char::from_digit((value - 1).into(), 36)
+                        .unwrap()
+                        .to_ascii_uppercase(),
+                }),
+                Notation::Numbers => {
+                    s.push_str(&value.to_string());
+                    if i % LENGTH == LENGTH - 1 {
+                        if i != NUM_CELLS - 1 {
+                            s.push('\n');
+                        }
+                    } else {
+                        s.push(',');
+                    }
+                }
+            }
+        }
+        s
+    }
+}
+
+// Uses the same characters as Notation::Letters with 0 for blanks.
+pub(crate) fn letter(value: u8) -> char {
+    match value {
+        0..=9 => std::char::from_digit(value.into(), 10).unwrap(),
+        _ => std::char::from_digit(value.into(), 36)
+            .unwrap()
+            .to_ascii_uppercase(),
+    }
+}
+
+// Picks numbers for text with commas, or with whitespace when the letters do not fill the
+// board but the text does split into numbers, such as 16x16 rows written as "10 16 3 ...".
+// Anything else is read as letters, so a compact layout with a cell missing reports its length.
+pub(crate) fn guess_notation(s: &str, num_cells: usize, length: usize) -> Notation {
+    if s.contains(',') {
+        return Notation::Numbers;
+    }
+    let letters = s.chars().filter(|&c| !is_drawing(c)).count();
+    if letters == num_cells || !s.contains(char::is_whitespace) {
+        return Notation::Letters;
+    }
+    let numbers = tokens(s, Notation::Numbers);
+    let numeric = numbers.iter().all(|(_, token)| match token[..] {
+        ['.'] | ['_'] | ['*'] => true,
+        _ => token
+            .iter()
+            .collect::<String>()
+            .parse::<usize>()
+            .map_or(false, |v| v <= length),
+    });
+    if numeric || numbers.len() == num_cells {
+        Notation::Numbers
+    } else {
+        Notation::Letters
+    }
+}
+
+// The characters of each cell with the position of the first one in the text.
+fn tokens(s: &str, notation: Notation) -> Vec<(usize, Vec<char>)> {
+    let mut tokens: Vec<(usize, Vec<char>)> = Vec::new();
+    let mut in_token = false;
+    // A - or + on its own between separators, which is a sign if a number follows it.
+    let mut sign = None;
+    let mut separated = true;
+    for (index, c) in s.chars().enumerate() {
+        if is_drawing(c) || (notation == Notation::Numbers && c == ',') {
+            in_token = false;
+        } else if in_token {
+            tokens.last_mut().unwrap().1.push(c);
+        } else if let (Some(sign), true) = (sign, c.is_ascii_digit()) {
+            // Keep the sign with its number so that it is rejected rather than drawn.
+            tokens.push((index - 1, vec![sign, c]));
+            in_token = true;
+        } else {
+            tokens.push((index, vec![c]));
+            // Letters take a single character each.
+            in_token = notation == Notation::Numbers;
+        }
+        sign = match c {
+            '-' | '+' if separated && notation == Notation::Numbers => Some(c),
+            _ => None,
+        };
+        separated = c.is_whitespace() || c == ',';
+    }
+    tokens
+}
+
+fn is_drawing(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '|' | '-' | '+' | '\u{2500}'..='\u{257F}')
+}
+
+fn letter_value(c: char) -> Option<u32> {
+    match c {
+        '.' | '_' | '*' => Some(0),
+        _ => c.to_digit(36),
+    }
+}
+
+fn zero_based_value(c: char) -> Option<u32> {
+    match c {
+        '.' | '_' | '*' => Some(0),
+        _ => c.to_digit(36).map(|v| v + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{ClassicSudoku, PuzzleRules};
+
+    #[test]
+    fn test_notations() {
+        let board: Board<16, 4, 2, 2> =
+            Board::parse("1.3.0...4...2..1", Notation::Letters).unwrap();
+        assert_eq!(board.format(Notation::ZeroBased), "0.2.....3...1..0");
+        assert_eq!(
+            board.format(Notation::Numbers),
+            "1,0,3,0\n0,0,0,0\n4,0,0,0\n2,0,0,1"
+        );
+        for &notation in [Notation::Letters, Notation::ZeroBased, Notation::Numbers].iter() {
+            assert_eq!(
+                Board::parse(&board.format(notation), notation).unwrap(),
+                board
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| s.parse::<Board<16, 4, 2, 2>>().unwrap_err();
+        assert_eq!(
+            parse("1,2,3"),
+            ParseError::WrongLength {
+                expected: 16,
+                found: 3
+            }
+        );
+        assert_eq!(
+            parse("1234|3412|21?3|4321"),
+            ParseError::InvalidCharacter {
+                character: '?',
+                index: 12,
+                row: 2,
+                column: 2
+            }
+        );
+        let error = parse("1234 3412 2193 4321");
+        assert_eq!(
+            error,
+            ParseError::DigitOutOfRange {
+                digit: "9".to_string(),
+                length: 4,
+                row: 2,
+                column: 2
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Digit 9 at row 3, column 3 is larger than 4"
+        );
+        // Letters are reported as written rather than by their value.
+        let letters = "A".repeat(81).parse::<Board<81, 9, 3, 3>>().unwrap_err();
+        assert_eq!(
+            letters.to_string(),
+            "Digit A at row 1, column 1 is larger than 9"
+        );
+        // Signs are not dropped along with the drawing characters.
+        assert_eq!(
+            Board::<16, 4, 2, 2>::parse("1,-1,0,0,0,0,0,0,0,0,0,0,0,0,0,0", Notation::Numbers)
+                .unwrap_err(),
+            ParseError::InvalidCharacter {
+                character: '-',
+                index: 2,
+                row: 0,
+                column: 1
+            }
+        );
+        assert!(
+            Board::<16, 4, 2, 2>::parse("1-2-3-4-0-0-0-0-0-0-0-0-0-0-0-0", Notation::Numbers)
+                .is_ok()
+        );
+        // A pasted grid with a cell missing reports its length rather than its first blank.
+        let missing = "53..7....\n6..195...\n.98....6.\n8...6...3\n4..8.3..1\n7...2...6\n\
+                       .6....28.\n...419..5\n....8..7";
+        assert_eq!(
+            missing.parse::<Board<81, 9, 3, 3>>().unwrap_err(),
+            ParseError::WrongLength {
+                expected: 81,
+                found: 80
+            }
+        );
+        assert_eq!(
+            Board::<16, 4, 2, 2>::parse("1 2 3 4 3 4 1 2 2 1 4 3 4 3 2 1e", Notation::Numbers)
+                .unwrap_err(),
+            ParseError::InvalidCharacter {
+                character: 'e',
+                index: 31,
+                row: 3,
+                column: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_tolerant_parsing() {
+        let expected: Board<81, 9, 3, 3> =
+            "530070000600195000098000060800060003400803001700020006060000280000419005000080079"
+                .parse()
+                .unwrap();
+        let pretty = "
+            5 3 . | . 7 . | . . .
+            6 . . | 1 9 5 | . . .
+            . 9 8 | . . . | . 6 .
+            ------+-------+------
+            8 . . | . 6 . | . . 3
+            4 . . | 8 . 3 | . . 1
+            7 . . | . 2 . | . . 6
+            ------+-------+------
+            . 6 . | . . . | 2 8 .
+            . . . | 4 1 9 | . . 5
+            . . . | . 8 . | . 7 9
+        ";
+        assert_eq!(pretty.parse::<Board<81, 9, 3, 3>>().unwrap(), expected);
+        let lines = "53__7____\n6**195___\n.98....6.\n8...6...3\n4..8.3..1\n7...2...6\n\
+                     .6....28.\n...419..5\n....8..79\n";
+        assert_eq!(lines.parse::<Board<81, 9, 3, 3>>().unwrap(), expected);
+        // Rows of letters are not mistaken for numbers when a cell is missing.
+        let short = lines.replacen('_', "", 1);
+        assert_eq!(guess_notation(&short, 81, 9), Notation::Letters);
+        assert_eq!(
+            guess_notation(&short.replace('_', "0"), 81, 9),
+            Notation::Letters
+        );
+        assert_eq!(guess_notation("10 16 3", 256, 16), Notation::Numbers);
+
+        let board: Board<16, 4, 2, 2> = "1 . 3 | . 4 . 1 _ | 0 2 * . | 1 . . 4".parse().unwrap();
+        assert_eq!(format!("{:?}", board), "1030401002001004");
+        let numbers = "+-----+-----+\n| 1 . | 3 0 |\n| 4 _ | 1 * |\n\
+                       +-----+-----+\n| 0 2 | . . |\n| 1 . | . 4 |";
+        assert_eq!(Board::parse(numbers, Notation::Numbers).unwrap(), board);
+    }
+
+    #[test]
+    fn test_render() {
+        let board: Board<36, 6, 3, 2> = "1.3.5.45.1.3234..2.12.4..2.4.1.4.2.3".parse().unwrap();
+        let ascii = "\
+1 . 3 | . 5 .
+4 5 . | 1 . 3
+------+------
+2 3 4 | . . 2
+. 1 2 | . 4 .
+------+------
+. 2 . | 4 . 1
+. 4 . | 2 . 3";
+        assert_eq!(board.to_string(), ascii);
+        assert_eq!(ascii.parse::<Board<36, 6, 3, 2>>().unwrap(), board);
+        let unicode = board.render(&RenderOptions::new().unicode(true));
+        assert_eq!(
+            unicode.lines().take(3).collect::<Vec<_>>(),
+            vec![
+                "┌───────┬───────┐",
+                "│ 1 . 3 │ . 5 . │",
+                "│ 4 5 . │ 1 . 3 │"
+            ]
+        );
+        assert_eq!(unicode.lines().last(), Some("└───────┴───────┘"));
+        assert_eq!(unicode.parse::<Board<36, 6, 3, 2>>().unwrap(), board);
+
+        // Pencil marks follow the digits without the candidates being computed first.
+        let board: Board<16, 4, 2, 2> = "1.3.....4...2..1".parse().unwrap();
+        // Givens are bracketed, unlike the single candidate at r3c3.
+        let pencil_marks = "\
+[1] 24  | [3] 24
+3   234 | 124 24
+--------+--------
+[4] 13  | 2   23
+[2] 3   | 4   [1]";
+        let options = RenderOptions::new().pencil_marks(true);
+        assert_eq!(board.render(&options), pencil_marks);
+        let mut board = crate::logic::with_candidates(&board, &ClassicSudoku {}).unwrap();
+        assert_eq!(board.render(&options), pencil_marks);
+        // Candidates removed from the board are left out too.
+        board.remove_candidate(1, NonZeroU8::new(4).unwrap());
+        assert!(board.render(&options).starts_with("[1] 2   | [3] 24\n"));
+    }
+
+    // A solved grid with square boxes whose rows are shifted copies of the first.
+    fn shifted<
+        const NUM_CELLS: usize,
+        const LENGTH: usize,
+        const BOX_WIDTH: usize,
+        const BOX_HEIGHT: usize,
+    >() -> Board<NUM_CELLS, LENGTH, BOX_WIDTH, BOX_HEIGHT> {
+        let mut cells = [Cell::Unfilled; NUM_CELLS];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            let (row, column) = (i / LENGTH, i % LENGTH);
+            let digit = (row * BOX_WIDTH + row / BOX_HEIGHT + column) % LENGTH + 1;
+            *cell = Cell::Filled((digit as u8).try_into().unwrap());
+        }
+        Board::from_cells(cells)
+    }
+
+    #[test]
+    fn test_large_grids() {
+        let solution: Board<256, 16, 4, 4> = shifted();
+        assert!(ClassicSudoku {}.is_valid(&solution));
+        let letters = solution.format(Notation::Letters);
+        assert!(letters.contains('G'));
+        assert_eq!(letters.parse::<Board<256, 16, 4, 4>>().unwrap(), solution);
+        assert_eq!(format!("{:?}", solution), letters);
+        let numbers = solution.format(Notation::Numbers);
+        assert!(numbers.contains("16"));
+        assert_eq!(numbers.parse::<Board<256, 16, 4, 4>>().unwrap(), solution);
+        let spaced = numbers.replace(',', " ");
+        assert_eq!(spaced.parse::<Board<256, 16, 4, 4>>().unwrap(), solution);
+
+        let solution: Board<625, 25, 5, 5> = shifted();
+        assert!(ClassicSudoku {}.is_valid(&solution));
+        let letters = format!("{:?}", solution);
+        assert!(letters.contains('P'));
+        assert_eq!(letters.parse::<Board<625, 25, 5, 5>>().unwrap(), solution);
+    }
+}